@@ -0,0 +1,123 @@
+use std::time::{Duration, Instant};
+
+use glam::{Mat4, Vec3A};
+use renderust::{
+    plane_buffer::plane_buffer::PlaneBufferCreateOption,
+    visual::{
+        drawing_buffer::DrawingBuffer,
+        rendering::{
+            face_culling::CullMode,
+            light_source::{LightSource, LightSourceKind},
+            matrix::{
+                camera_matrixes::CameraMatrixes, projection_matrix::create_projection_matrix,
+                view_matrix::create_view_matrix, viewport_matrix::create_view_port_matrix,
+            },
+            shadow::shadow_settings::ShadowSettings,
+            tiling::tiled_rasterizer::TiledRasterizer,
+            wavefront_obj::{
+                wavefront_obj_rendering::render_wavefront_mesh,
+                wavefront_render_model::WavefrontRenderModel,
+            },
+        },
+    },
+    wavefront::{wavefront_obj::WavefrontObj, wavefront_obj_source::WaveFrontObjSource},
+};
+
+const BUFFER_SIZE: usize = 1000;
+const Z_BUFFER_SIZE: f32 = 255.0;
+const WARMUP_FRAMES: u32 = 3;
+const FRAMES: u32 = 20;
+
+const DIABLO_MODEL: WaveFrontObjSource = WaveFrontObjSource::new(
+    "./resources/diablo3_pose.obj",
+    Some("./resources/diablo3_pose_diffuse.tga"),
+    Some("./resources/diablo3_pose_nm_tangent.tga"),
+    Some("./resources/diablo3_pose_spec.tga"),
+    Some("./resources/diablo3_pose_glow.tga"),
+);
+
+fn bench_frames(model: &WavefrontRenderModel, lights: &[LightSource], use_simd: bool) -> Duration {
+    // Single thread, so that only fragment processing differs between runs
+    let rasterizer = TiledRasterizer::new(1, 64, use_simd);
+    let mut draw_buffer =
+        DrawingBuffer::new(BUFFER_SIZE, BUFFER_SIZE, PlaneBufferCreateOption::Blank);
+
+    let size = BUFFER_SIZE as f32;
+    let camera = CameraMatrixes {
+        viewport_matrix: create_view_port_matrix(
+            size * 0.125,
+            size * 0.125,
+            size / 1.25,
+            size / 1.25,
+            Z_BUFFER_SIZE,
+        ),
+        projection: create_projection_matrix(5.0),
+        view_matrix: create_view_matrix(5.0 * Vec3A::Z, Vec3A::ZERO, Vec3A::Y),
+        rotation_matrix: Mat4::IDENTITY,
+    };
+
+    let mut render_frame = || {
+        draw_buffer.get_z_buffer_mut().clean_with(&f32::MIN);
+        draw_buffer.clean();
+        render_wavefront_mesh(
+            model,
+            &mut draw_buffer,
+            &rasterizer,
+            lights.to_vec(),
+            &camera,
+        );
+    };
+
+    for _ in 0..WARMUP_FRAMES {
+        render_frame();
+    }
+
+    // Fastest frame, as the slower ones mostly measure the other load of the machine
+    (0..FRAMES)
+        .map(|_| {
+            let start = Instant::now();
+            render_frame();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() -> Result<(), String> {
+    let mut model: WavefrontRenderModel = WavefrontObj::from_sources_struct(&DIABLO_MODEL)?.into();
+    model.use_normal_map = true;
+    model.use_spec_map = true;
+    model.use_glow_map = true;
+    model.cull_mode = CullMode::Back;
+
+    let lights = vec![
+        LightSource::new(
+            LightSourceKind::Linear {
+                dir: Vec3A::new(0.0, 2.0, 1.0).normalize(),
+                shadow: ShadowSettings::default(),
+                shadow_maps: Vec::new(),
+            },
+            Vec3A::ONE * 0.4,
+            1.0,
+        ),
+        LightSource::new(
+            LightSourceKind::Linear {
+                dir: Vec3A::Y,
+                shadow: ShadowSettings::default(),
+                shadow_maps: Vec::new(),
+            },
+            Vec3A::ONE * 0.4,
+            1.0,
+        ),
+        LightSource::new(LightSourceKind::Ambient, Vec3A::ONE * 0.05, 1.0),
+    ];
+
+    let scalar = bench_frames(&model, &lights, false);
+    let simd = bench_frames(&model, &lights, true);
+
+    println!("scalar: {:?} per frame (fastest)", scalar);
+    println!("simd:   {:?} per frame (fastest)", simd);
+    println!("speedup: {:.2}x", scalar.as_secs_f64() / simd.as_secs_f64());
+
+    Ok(())
+}
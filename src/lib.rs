@@ -0,0 +1,11 @@
+#![allow(non_snake_case)]
+
+#[macro_use]
+extern crate derive_builder;
+
+pub mod math;
+pub mod parsing;
+pub mod plane_buffer;
+pub mod ui;
+pub mod visual;
+pub mod wavefront;
@@ -1,17 +1,10 @@
-#![allow(non_snake_case)]
-
-#[macro_use]
-extern crate derive_builder;
-
-pub mod math;
-pub mod parsing;
-pub mod plane_buffer;
-pub mod ui;
-pub mod visual;
-pub mod wavefront;
-
-use ui::render_window::render_window::open_render_window;
-use wavefront::{wavefront_obj::WavefrontObj, wavefront_obj_source::WaveFrontObjSource};
+use renderust::{
+    ui::render_window::render_window::{open_render_window, RenderWindowSettings},
+    visual::rendering::{
+        face_culling::CullMode, wavefront_obj::wavefront_render_model::WavefrontRenderModel,
+    },
+    wavefront::{wavefront_obj::WavefrontObj, wavefront_obj_source::WaveFrontObjSource},
+};
 
 const BUFFER_WIDTH: usize = 1000;
 const BUFFER_HEIGHT: usize = 1000;
@@ -19,11 +12,17 @@ const BUFFER_HEIGHT: usize = 1000;
 const WINDOW_WIDTH: usize = 1000;
 const WINDOW_HEIGHT: usize = 1000;
 
+// Scene is rendered at this many times the buffer resolution and then downsampled
+const SUPERSAMPLING: usize = 1;
+
 const Z_BUFFER_SIZE: f32 = 255.0;
 
+// 0 — use all available cores, 1 — render without tiling
+const RENDER_THREADS: usize = 0;
+
 const AFRO_MODEL: WaveFrontObjSource = WaveFrontObjSource::new(
     "./resources/african_head.obj",
-    "./resources/african_head_diffuse.tga",
+    Some("./resources/african_head_diffuse.tga"),
     Some("./resources/african_head_nm_tangent.tga"),
     Some("./resources/african_head_spec.tga"),
     None
@@ -31,7 +30,7 @@ const AFRO_MODEL: WaveFrontObjSource = WaveFrontObjSource::new(
 
 const FLOOR_MODEL: WaveFrontObjSource = WaveFrontObjSource::new(
     "./resources/floor.obj",
-    "./resources/floor_diffuse.tga",
+    Some("./resources/floor_diffuse.tga"),
     Some("./resources/floor_nm_tangent.tga"),
     None,
     None
@@ -39,7 +38,7 @@ const FLOOR_MODEL: WaveFrontObjSource = WaveFrontObjSource::new(
 
 const DIABLO_MODEL: WaveFrontObjSource = WaveFrontObjSource::new(
     "./resources/diablo3_pose.obj",
-    "./resources/diablo3_pose_diffuse.tga",
+    Some("./resources/diablo3_pose_diffuse.tga"),
     Some("./resources/diablo3_pose_nm_tangent.tga"),
     Some("./resources/diablo3_pose_spec.tga"),
     Some("./resources/diablo3_pose_glow.tga")
@@ -50,13 +49,21 @@ fn main() -> Result<(), String> {
     let floor_obj = WavefrontObj::from_sources_struct(&FLOOR_MODEL)?;
     let diablo_obj = WavefrontObj::from_sources_struct(&DIABLO_MODEL)?;
 
+    // Diablo is a closed mesh, so its back faces are never visible
+    let mut diablo: WavefrontRenderModel = diablo_obj.into();
+    diablo.cull_mode = CullMode::Back;
+
     open_render_window(
-        BUFFER_WIDTH,
-        BUFFER_HEIGHT,
-        WINDOW_WIDTH,
-        WINDOW_HEIGHT,
-        Z_BUFFER_SIZE,
-        vec![diablo_obj.into()],
+        RenderWindowSettings {
+            buffer_width: BUFFER_WIDTH,
+            buffer_height: BUFFER_HEIGHT,
+            window_width: WINDOW_WIDTH,
+            window_height: WINDOW_HEIGHT,
+            z_buffer_size: Z_BUFFER_SIZE,
+            render_threads: RENDER_THREADS,
+            supersampling: SUPERSAMPLING,
+        },
+        vec![diablo],
     );
 
     Ok(())
@@ -8,7 +8,6 @@ macro_rules! derive_self_wrapping_add {
             fn add(self, rhs: Self) -> Self::Output {
                 $t {
                     $($field: self.$field.wrapping_add(rhs.$field),)+
-                    ..self
                 }
             }
         }
@@ -25,7 +24,6 @@ macro_rules! derive_self_wrapping_sub {
             fn sub(self, rhs: Self) -> Self::Output {
                 $t {
                     $($field: self.$field.wrapping_sub(rhs.$field),)+
-                    ..self
                 }
             }
         }
@@ -42,7 +40,6 @@ macro_rules! derive_self_add {
             fn add(self, rhs: Self) -> Self::Output {
                 $t {
                     $($field: self.$field + rhs.$field,)+
-                    ..self
                 }
             }
         }
@@ -59,7 +56,6 @@ macro_rules! derive_self_sub {
             fn sub(self, rhs: Self) -> Self::Output {
                 $t {
                     $($field: self.$field - rhs.$field,)+
-                    ..self
                 }
             }
         }
@@ -74,7 +70,6 @@ macro_rules! derive_mul_by {
             fn mul(self, rhs: $multiplier_type) -> Self::Output {
                 $t {
                     $($field: (self.$field * rhs as $source_type),)+
-                    ..self
                 }
             }
         }
@@ -89,7 +84,6 @@ macro_rules! derive_div_by {
             fn div(self, rhs: $divider_type) -> Self::Output {
                 $t {
                     $($field: (self.$field / rhs as $source_type),)+
-                    ..self
                 }
             }
         }
@@ -1,4 +1,5 @@
 pub mod rect_size;
+pub mod rect;
 pub mod primitives {
     pub mod line;
 }
@@ -0,0 +1,45 @@
+use std::ops::Range;
+
+use super::rect_size::RectSize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub size: RectSize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            size: RectSize { width, height },
+        }
+    }
+
+    pub fn get_x_range(&self) -> Range<usize> {
+        self.x..self.x + self.size.width
+    }
+
+    pub fn get_y_range(&self) -> Range<usize> {
+        self.y..self.y + self.size.height
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.get_x_range().contains(&x) && self.get_y_range().contains(&y)
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.size.width
+            && other.x < self.x + self.size.width
+            && self.y < other.y + other.size.height
+            && other.y < self.y + self.size.height
+    }
+}
+
+impl From<RectSize> for Rect {
+    fn from(size: RectSize) -> Self {
+        Rect { x: 0, y: 0, size }
+    }
+}
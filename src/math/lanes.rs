@@ -0,0 +1,111 @@
+use glam::{Vec3A, Vec4};
+
+use crate::{derive_self_add, derive_self_sub};
+
+/// Number of pixels processed together by the SIMD paths, one per `Vec4` lane
+pub const LANES: usize = 4;
+
+/// Three-component vectors in structure-of-arrays layout, one per lane
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3x4 {
+    pub x: Vec4,
+    pub y: Vec4,
+    pub z: Vec4,
+}
+
+derive_self_add!(Vec3x4, x, y, z);
+derive_self_sub!(Vec3x4, x, y, z);
+
+impl Vec3x4 {
+    pub const ZERO: Vec3x4 = Vec3x4 {
+        x: Vec4::ZERO,
+        y: Vec4::ZERO,
+        z: Vec4::ZERO,
+    };
+
+    pub fn splat(v: Vec3A) -> Vec3x4 {
+        Vec3x4 {
+            x: Vec4::splat(v.x),
+            y: Vec4::splat(v.y),
+            z: Vec4::splat(v.z),
+        }
+    }
+
+    pub fn from_lanes(lanes: [Vec3A; LANES]) -> Vec3x4 {
+        Vec3x4 {
+            x: Vec4::from(lanes.map(|v| v.x)),
+            y: Vec4::from(lanes.map(|v| v.y)),
+            z: Vec4::from(lanes.map(|v| v.z)),
+        }
+    }
+
+    /// Values of the triangle vertices weighted by the barycentric weights of each lane
+    pub fn interpolate(values: [Vec3A; 3], weights: &[Vec4; 3]) -> Vec3x4 {
+        Vec3x4::splat(values[0]).scale(weights[0])
+            + Vec3x4::splat(values[1]).scale(weights[1])
+            + Vec3x4::splat(values[2]).scale(weights[2])
+    }
+
+    pub fn lane(&self, i: usize) -> Vec3A {
+        Vec3A::new(self.x[i], self.y[i], self.z[i])
+    }
+
+    pub fn set_lane(&mut self, i: usize, v: Vec3A) {
+        self.x[i] = v.x;
+        self.y[i] = v.y;
+        self.z[i] = v.z;
+    }
+
+    pub fn dot(&self, rhs: &Vec3x4) -> Vec4 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    pub fn cross(&self, rhs: &Vec3x4) -> Vec3x4 {
+        Vec3x4 {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        }
+    }
+
+    pub fn scale(&self, factor: Vec4) -> Vec3x4 {
+        Vec3x4 {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    /// Component-wise product with the same vector in every lane
+    pub fn mul_splat(&self, v: Vec3A) -> Vec3x4 {
+        Vec3x4 {
+            x: self.x * v.x,
+            y: self.y * v.y,
+            z: self.z * v.z,
+        }
+    }
+
+    /// Lanes of zero length stay zero
+    pub fn normalize_or_zero(&self) -> Vec3x4 {
+        let length_squared = self.dot(self);
+        let inv_length = Vec4::select(
+            length_squared.cmpgt(Vec4::ZERO),
+            Vec4::ONE / sqrt_lanes(length_squared),
+            Vec4::ZERO,
+        );
+        self.scale(inv_length)
+    }
+}
+
+pub fn sqrt_lanes(v: Vec4) -> Vec4 {
+    Vec4::new(v.x.sqrt(), v.y.sqrt(), v.z.sqrt(), v.w.sqrt())
+}
+
+/// Lanes outside of the mask are left zero
+pub fn powf_lanes(base: Vec4, exponent: Vec4, mask: u32) -> Vec4 {
+    let mut result = Vec4::ZERO;
+    for i in (0..LANES).filter(|i| mask & (1 << i) != 0) {
+        result[i] = base[i].powf(exponent[i]);
+    }
+    result
+}
@@ -1,4 +1,5 @@
 pub mod geometry;
 pub mod derive_arithmetics;
 pub mod interpolation;
+pub mod lanes;
 pub mod rotation;
\ No newline at end of file
@@ -5,10 +5,9 @@ use std::{
 };
 
 use glam::{UVec3, Vec3A};
-use image::{DynamicImage, GenericImageView};
 
 use crate::{
-    plane_buffer::plane_buffer::PlaneBuffer,
+    visual::rendering::texture::texture::Texture,
     wavefront::{wavefront_obj::WavefrontObj, wavefront_obj_source::WaveFrontObjSource},
 };
 
@@ -18,57 +17,27 @@ use super::{
 
 const LINE_ENDINGS: [&'static str; 2] = ["\r\n", "\n"];
 
-fn normal_map_vecs_from_rgb(normal_map_img: DynamicImage) -> PlaneBuffer<Vec3A> {
-    let normals: Vec<Vec3A> = normal_map_img
-        .to_rgb8()
-        .iter()
-        .map(|c| (*c as f32 / 255.0) * 2.0 - 1.0)
-        .collect::<Vec<f32>>()
-        .chunks_exact(3)
-        .map(Vec3A::from_slice)
-        .map(Vec3A::normalize)
-        .collect();
-
-    PlaneBuffer::new(
-        normal_map_img.width() as usize,
-        normal_map_img.height() as usize,
-        crate::plane_buffer::plane_buffer::PlaneBufferCreateOption::RawSource(normals),
-    )
-}
-
 impl WavefrontObj {
     pub fn from_file(
         model_source: &File,
-        texture_source: &File,
+        texture_source: Option<&File>,
         normal_map_source: Option<&File>,
         spec_map_source: Option<&File>,
         glow_map_source: Option<&File>,
     ) -> Result<WavefrontObj, String> {
         let mut line = String::new();
-        let texture_reader = BufReader::new(texture_source);
-        let texture = image::load(texture_reader, image::ImageFormat::Tga)
-            .unwrap()
-            .flipv();
-
-        let normal_map = normal_map_source
-            .map(BufReader::new)
-            .map(|reader| image::load(reader, image::ImageFormat::Tga).unwrap())
-            .map(normal_map_vecs_from_rgb);
-
-        let spec_map = spec_map_source.map(BufReader::new).map(|reader| {
-            image::load(reader, image::ImageFormat::Tga)
-                .unwrap()
-                .flipv()
-        });
-
-        let glow_map = glow_map_source.map(BufReader::new).map(|reader| {
-            image::load(reader, image::ImageFormat::Tga)
-                .unwrap()
-                .flipv()
-        });
+        // Models without the diffuse texture, e.g. point clouds, are white
+        let texture = match texture_source {
+            Some(texture_source) => load_tga(texture_source)?,
+            None => Texture::from_texels(1, 1, vec![[255; 4]])?,
+        };
+        let normal_map = normal_map_source.map(load_tga).transpose()?;
+        let spec_map = spec_map_source.map(load_tga).transpose()?;
+        let glow_map = glow_map_source.map(load_tga).transpose()?;
 
         let mut wavefront_obj = WavefrontObj {
             vertices: Default::default(),
+            vertex_colors: Default::default(),
             vertex_textures: Default::default(),
             vertex_normals: Default::default(),
             faces: Default::default(),
@@ -76,8 +45,11 @@ impl WavefrontObj {
             normal_map,
             spec_map,
             glow_map,
+            metallic_roughness_map: None,
+            occlusion_map: None,
         };
         let mut buff_reader = BufReader::new(model_source);
+        let mut has_vertex_colors = false;
 
         loop {
             match buff_reader.read_line(&mut line) {
@@ -101,7 +73,19 @@ impl WavefrontObj {
                         line.chars().skip(first_letters.len() + 1).collect();
                     let vec3f = str_parse_vec3(&floats_string)?;
                     match first_letters.as_str() {
-                        "v" => wavefront_obj.vertices.push(vec3f),
+                        "v" => {
+                            wavefront_obj.vertices.push(vec3f);
+                            let color_words: Vec<&str> =
+                                floats_string.split_whitespace().skip(3).collect();
+                            // Colors stay aligned with the vertices, uncolored ones are white
+                            let color = if color_words.len() >= 3 {
+                                has_vertex_colors = true;
+                                str_parse_vec3(&color_words.join(" "))?
+                            } else {
+                                Vec3A::ONE
+                            };
+                            wavefront_obj.vertex_colors.push(color);
+                        }
                         "vt" => wavefront_obj.vertex_textures.push(vec3f),
                         "vn" => wavefront_obj.vertex_normals.push(vec3f),
                         _ => (),
@@ -122,19 +106,25 @@ impl WavefrontObj {
             line.clear();
         }
 
+        if !has_vertex_colors {
+            wavefront_obj.vertex_colors.clear();
+        }
+
         Ok(wavefront_obj)
     }
 
     pub fn from_paths(
         model_source_path: &Path,
-        texture_source_path: &Path,
+        texture_source_path: Option<&Path>,
         normal_map_source_path: Option<&Path>,
         spec_map_source_path: Option<&Path>,
         glow_map_source_path: Option<&Path>,
     ) -> Result<WavefrontObj, String> {
         let wavefront_obj_file = File::open(model_source_path)
             .map_err(|e| format!("Error opening model file: {:?}", e))?;
-        let texture_file = File::open(texture_source_path)
+        let texture_file = texture_source_path
+            .map(File::open)
+            .transpose()
             .map_err(|e| format!("Error opening texture file: {:?}", e))?;
 
         let normal_map_file = normal_map_source_path
@@ -154,7 +144,7 @@ impl WavefrontObj {
 
         WavefrontObj::from_file(
             &wavefront_obj_file,
-            &texture_file,
+            texture_file.as_ref(),
             normal_map_file.as_ref(),
             spec_map_file.as_ref(),
             glow_map_file.as_ref(),
@@ -162,15 +152,76 @@ impl WavefrontObj {
         .map_err(|e| format!("Error parsing file: {:?}", e))
     }
 
+    /// Loads an additional map of any supported image format, e.g. for the metallic-roughness shading
+    pub fn load_map(map_source_path: &Path) -> Result<Texture, String> {
+        let map =
+            image::open(map_source_path).map_err(|e| format!("Error opening map file: {:?}", e))?;
+        Texture::from_image(&map.flipv())
+    }
+
     pub fn from_sources_struct(
         wavefront_obj_source: &WaveFrontObjSource,
     ) -> Result<WavefrontObj, String> {
         WavefrontObj::from_paths(
             wavefront_obj_source.model_path.as_ref(),
-            wavefront_obj_source.texture_path.as_ref(),
+            wavefront_obj_source.texture_path.map(|s| s.as_ref()),
             wavefront_obj_source.normal_map_path.map(|s| s.as_ref()),
             wavefront_obj_source.spec_map_path.map(|s| s.as_ref()),
             wavefront_obj_source.glow_map_path.map(|s| s.as_ref()),
         )
     }
 }
+
+fn load_tga(source: &File) -> Result<Texture, String> {
+    let image = image::load(BufReader::new(source), image::ImageFormat::Tga)
+        .map_err(|e| format!("Error loading texture: {:?}", e))?;
+    Texture::from_image(&image.flipv())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use glam::{Vec3A, Vec4};
+
+    use crate::wavefront::wavefront_obj::WavefrontObj;
+
+    fn write_model(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn point_cloud_loads_without_texture() {
+        let path = write_model(
+            "renderust_point_cloud.obj",
+            "v 0 0 0 1 0 0\nv 1 0 0 0 1 0\nv 0 1 0 0 0 1\n",
+        );
+        let obj = WavefrontObj::from_paths(&path, None, None, None, None).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(obj.vertices.len(), 3);
+        assert_eq!(obj.vertex_colors[2], Vec3A::Z);
+        assert!(obj.faces.is_empty());
+        assert_eq!(obj.texture.get_level(0).fetch_texel(0, 0), Vec4::ONE);
+    }
+
+    #[test]
+    fn vertex_colors_stay_aligned() {
+        let path = write_model(
+            "renderust_mixed_colors.obj",
+            "v 0 0 0\nv 1 0 0 0 1 0\nv 0 1 0\n",
+        );
+        let obj = WavefrontObj::from_paths(&path, None, None, None, None).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(obj.vertex_colors, vec![Vec3A::ONE, Vec3A::Y, Vec3A::ONE]);
+
+        let path = write_model("renderust_no_colors.obj", "v 0 0 0\nv 1 0 0\n");
+        let obj = WavefrontObj::from_paths(&path, None, None, None, None).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(obj.vertex_colors.is_empty());
+    }
+}
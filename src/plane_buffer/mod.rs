@@ -1,3 +1,4 @@
 pub mod plane_buffer;
 pub mod plane_buffer_indexing;
+pub mod plane_buffer_region;
 pub mod plane_buffer_resizing;
\ No newline at end of file
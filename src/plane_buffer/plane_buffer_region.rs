@@ -0,0 +1,33 @@
+use crate::math::geometry::rect::Rect;
+
+use super::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption};
+
+impl<T: Default + Copy> PlaneBuffer<T> {
+    fn get_row_offset(&self, x: usize, y: usize) -> usize {
+        x + (self.get_height() - y - 1) * self.get_width()
+    }
+
+    pub fn copy_region(&self, region: &Rect) -> PlaneBuffer<T> {
+        let mut copy = PlaneBuffer::new(
+            region.size.width,
+            region.size.height,
+            PlaneBufferCreateOption::Blank,
+        );
+        for y in 0..region.size.height {
+            let src_offset = self.get_row_offset(region.x, region.y + y);
+            let dst_offset = copy.get_row_offset(0, y);
+            copy.get_buffer_mut()[dst_offset..dst_offset + region.size.width]
+                .copy_from_slice(&self.get_buffer()[src_offset..src_offset + region.size.width]);
+        }
+        copy
+    }
+
+    pub fn paste_region(&mut self, region: &Rect, source: &PlaneBuffer<T>) {
+        for y in 0..region.size.height {
+            let src_offset = source.get_row_offset(0, y);
+            let dst_offset = self.get_row_offset(region.x, region.y + y);
+            self.get_buffer_mut()[dst_offset..dst_offset + region.size.width]
+                .copy_from_slice(&source.get_buffer()[src_offset..src_offset + region.size.width]);
+        }
+    }
+}
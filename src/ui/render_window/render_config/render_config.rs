@@ -1,6 +1,19 @@
 use glam::{Mat4, Vec3A};
 
-use crate::visual::rendering::{light_source::LightSource, wavefront_obj::wavefront_render_model::WavefrontRenderModel, matrix::view_matrix::create_view_matrix};
+use crate::visual::{
+    color::color::Color,
+    multisampling::SampleCount,
+    rendering::{
+        anti_aliasing::supersampling::DownsampleFilter, light_source::LightSource,
+        line::line_style::LineStyle,
+        matrix::{
+            camera_matrixes::CameraMatrixes,
+            projection_matrix::create_reversed_z_projection_matrix,
+            view_matrix::create_view_matrix,
+        },
+        wavefront_obj::wavefront_render_model::WavefrontRenderModel,
+    },
+};
 
 #[derive(Clone, Debug, Builder)]
 pub struct RenderConfig {
@@ -8,6 +21,12 @@ pub struct RenderConfig {
     pub camera: CameraConfig,
     pub lights: Vec<LightSource>,
     pub ambient_occlusion: AmbientOcclusionConfig,
+    pub rasterization: RasterizationConfig,
+    pub anti_aliasing: AntiAliasingConfig,
+    pub wireframe: WireframeConfig,
+    pub transparency: TransparencyConfig,
+    pub render_targets: RenderTargetConfig,
+    pub outline: OutlineConfig,
     #[builder(setter(custom))]
     pub transform_matrixes: TransformMatrixes,
     pub models: Vec<WavefrontRenderModel>
@@ -25,6 +44,9 @@ pub struct CameraConfig {
     pub pitch: f32,
     pub yaw: f32,
     pub distance: f32,
+    /// Clip planes as distances from the viewer, the depth between them fills the z-buffer
+    pub near: f32,
+    pub far: f32,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -34,6 +56,88 @@ pub struct AmbientOcclusionConfig {
     pub intensity: f32
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct RasterizationConfig {
+    pub threads: usize,
+    pub tile_size: usize,
+    pub use_simd: bool,
+    pub occlusion_culling: bool,
+    pub depth_pre_pass: bool,
+    /// Opaque meshes are lit once per pixel from the G-buffer
+    pub deferred_shading: bool,
+    pub msaa: SampleCount,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AntiAliasingConfig {
+    pub fxaa: bool,
+    pub supersampling: usize,
+    pub downsample_filter: DownsampleFilter,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireframeMode {
+    Off,
+    Overlay,
+    Only,
+}
+
+impl WireframeMode {
+    pub fn next(&self) -> WireframeMode {
+        match self {
+            WireframeMode::Off => WireframeMode::Overlay,
+            WireframeMode::Overlay => WireframeMode::Only,
+            WireframeMode::Only => WireframeMode::Off,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct WireframeConfig {
+    pub mode: WireframeMode,
+    pub hidden_line_removal: bool,
+    pub depth_offset: f32,
+    pub line_style: LineStyle,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransparencyConfig {
+    /// Weighted blended OIT instead of back to front sorting for alpha blended models
+    pub order_independent: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetView {
+    /// Lit scene of the drawing buffer
+    Scene,
+    /// Shading normal target of the multiple render target pass
+    Normals,
+}
+
+impl TargetView {
+    pub fn next(&self) -> TargetView {
+        match self {
+            TargetView::Scene => TargetView::Normals,
+            TargetView::Normals => TargetView::Scene,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RenderTargetConfig {
+    pub view: TargetView,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OutlineConfig {
+    /// Mesh models mark the stencil plane and get outlined around the mask
+    pub apply: bool,
+    pub stencil_reference: u8,
+    /// In the model space units
+    pub width: f32,
+    pub color: Color,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct TransformMatrixes {
     pub view_matrix: Mat4,
@@ -41,16 +145,27 @@ pub struct TransformMatrixes {
     pub viewport_matrix: Mat4,
 }
 
+impl TransformMatrixes {
+    pub fn with_rotation(&self, rotation_matrix: Mat4) -> CameraMatrixes {
+        CameraMatrixes {
+            viewport_matrix: self.viewport_matrix,
+            projection: self.projection,
+            view_matrix: self.view_matrix,
+            rotation_matrix,
+        }
+    }
+}
+
 impl RenderConfigBuilder {
     pub fn transform_matrixes(&mut self, viewport_matrix: Mat4) -> &mut Self {
         let mut new = self;
         let LookConfig { from, to, up } = new.look.unwrap();
-        let mut transform_matrixes = TransformMatrixes {
+        let CameraConfig { near, far, .. } = new.camera.unwrap();
+        let transform_matrixes = TransformMatrixes {
             view_matrix: create_view_matrix(from, to, up),
-            projection: Mat4::IDENTITY,
+            projection: create_reversed_z_projection_matrix(from.distance(to), near, far),
             viewport_matrix,
         };
-        transform_matrixes.projection.col_mut(2)[3] = -1.0 / from.distance(to);
         new.transform_matrixes = Some(transform_matrixes);
         new
     }
@@ -1,21 +1,44 @@
 use std::time::Instant;
 
-use glam::{Mat4, Quat, Vec2, Vec3, Vec3A};
+use glam::{Vec2, Vec3A, Vec4};
 use minifb::{Key, KeyRepeat, ScaleMode, Window, WindowOptions};
 
 use crate::{
     math::rotation::create_rotation_matrix,
     plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
     visual::{
+        color::color::Color,
         drawing_buffer::DrawingBuffer,
+        multisampling::SampleCount,
         rendering::{
             ambient_occlusion::render_ambient_occlusion,
+            anti_aliasing::{
+                fxaa::apply_fxaa,
+                supersampling::{downsample, DownsampleFilter},
+            },
+            deferred::{deferred_lighting::render_deferred_lighting, g_buffer::GBuffer},
             light_source::{LightSource, LightSourceKind},
-            matrix::viewport_matrix::create_view_port_matrix,
+            line::line_style::LineStyle,
+            matrix::{
+                camera_matrixes::CameraMatrixes, projection_matrix::get_projection_eye,
+                viewport_matrix::create_view_port_matrix,
+            },
+            render_target::{
+                framebuffer::Framebuffer,
+                render_target::{RenderTarget, RenderTargetFormat},
+            },
+            shadow::{shadow_pass::render_shadow_maps, shadow_settings::ShadowSettings},
+            tiling::tiled_rasterizer::TiledRasterizer,
             wavefront_obj::{
                 wavefront_obj_depth::render_wavefront_depth,
-                wavefront_obj_rendering::render_wavefront_mesh,
-                wavefront_render_model::WavefrontRenderModel,
+                wavefront_obj_points::render_wavefront_points,
+                wavefront_obj_processing::calculate_model_depth,
+                wavefront_obj_rendering::{
+                    render_wavefront_depth_pre_pass, render_wavefront_framebuffer,
+                    render_wavefront_g_buffer, render_wavefront_grid, render_wavefront_mesh,
+                    render_wavefront_outline, rotate_lights,
+                },
+                wavefront_render_model::{RenderMode, WavefrontRenderModel},
             },
         },
     },
@@ -23,7 +46,9 @@ use crate::{
 
 use super::{
     render_config::render_config::{
-        AmbientOcclusionConfig, CameraConfig, LookConfig, RenderConfigBuilder, TransformMatrixes,
+        AmbientOcclusionConfig, AntiAliasingConfig, CameraConfig, LookConfig, OutlineConfig,
+        RasterizationConfig, RenderConfigBuilder, RenderTargetConfig, TargetView,
+        TransparencyConfig, WireframeConfig, WireframeMode,
     },
     scene_control::{
         render_config_control::handle_render_config_controls,
@@ -32,16 +57,51 @@ use super::{
     },
 };
 
-pub fn open_render_window(
-    buffer_width: usize,
-    buffer_height: usize,
-    window_width: usize,
-    window_height: usize,
-    z_buffer_size: f32,
-    models: Vec<WavefrontRenderModel>,
-) {
-    let mut draw_buffer =
-        DrawingBuffer::new(buffer_width, buffer_height, PlaneBufferCreateOption::Blank);
+#[derive(Clone, Copy, Debug)]
+pub struct RenderWindowSettings {
+    pub buffer_width: usize,
+    pub buffer_height: usize,
+    pub window_width: usize,
+    pub window_height: usize,
+    pub z_buffer_size: f32,
+    /// 0 — use all available cores, 1 — render without tiling
+    pub render_threads: usize,
+    /// Scene is rendered at this many times the buffer resolution and then downsampled
+    pub supersampling: usize,
+}
+
+const COLOR_TARGET: &str = "color";
+const NORMAL_TARGET: &str = "normal";
+
+pub fn open_render_window(settings: RenderWindowSettings, models: Vec<WavefrontRenderModel>) {
+    let RenderWindowSettings {
+        buffer_width,
+        buffer_height,
+        window_width,
+        window_height,
+        z_buffer_size,
+        render_threads,
+        supersampling,
+    } = settings;
+    let supersampling = supersampling.max(1);
+    let mut draw_buffer = DrawingBuffer::new(
+        buffer_width * supersampling,
+        buffer_height * supersampling,
+        PlaneBufferCreateOption::Blank,
+    );
+    // The reversed-Z projection fits the depth between the clip planes into the z-buffer
+    draw_buffer.set_depth_clip_range(0.0..z_buffer_size);
+    let mut g_buffer = GBuffer::new(draw_buffer.get_width(), draw_buffer.get_height());
+    // Outputs of `DefaultShader` as a multiple render target shader
+    let mut framebuffer = Framebuffer::new(draw_buffer.get_width(), draw_buffer.get_height());
+    framebuffer
+        .add_target(COLOR_TARGET, RenderTargetFormat::Color)
+        .unwrap();
+    framebuffer
+        .add_target(NORMAL_TARGET, RenderTargetFormat::Vec3A)
+        .unwrap();
+    let mut output_buffer =
+        PlaneBuffer::<Color>::new(buffer_width, buffer_height, PlaneBufferCreateOption::Blank);
 
     let (w_f32, h_f32) = (
         draw_buffer.get_width() as f32,
@@ -58,13 +118,15 @@ pub fn open_render_window(
             pitch: 0.0,
             yaw: 0.0,
             distance: 5.0,
+            near: 1.0,
+            far: 10.0,
         })
         .lights(vec![
             LightSource::new(
                 LightSourceKind::Linear {
                     dir: Vec3A::new(0.0, 2.0, 1.0).normalize(),
-                    shadow_buffer: None,
-                    transform_matrix: None,
+                    shadow: ShadowSettings::default(),
+                    shadow_maps: Vec::new(),
                 },
                 Vec3A::ONE * 0.4,
                 1.0,
@@ -72,8 +134,8 @@ pub fn open_render_window(
             LightSource::new(
                 LightSourceKind::Linear {
                     dir: Vec3A::Y,
-                    shadow_buffer: None,
-                    transform_matrix: None,
+                    shadow: ShadowSettings::default(),
+                    shadow_maps: Vec::new(),
                 },
                 Vec3A::ONE * 0.4,
                 1.0,
@@ -85,6 +147,41 @@ pub fn open_render_window(
             effect_radius: 10.0,
             intensity: 0.5,
         })
+        .rasterization(RasterizationConfig {
+            threads: render_threads,
+            tile_size: 64,
+            use_simd: true,
+            occlusion_culling: false,
+            depth_pre_pass: false,
+            deferred_shading: false,
+            msaa: SampleCount::X1,
+        })
+        .anti_aliasing(AntiAliasingConfig {
+            fxaa: false,
+            supersampling,
+            downsample_filter: DownsampleFilter::Box,
+        })
+        .wireframe(WireframeConfig {
+            mode: WireframeMode::Off,
+            hidden_line_removal: true,
+            depth_offset: 0.1,
+            line_style: LineStyle {
+                anti_aliased: true,
+                ..LineStyle::new(Color::from_rgb(255, 255, 255))
+            },
+        })
+        .transparency(TransparencyConfig {
+            order_independent: false,
+        })
+        .render_targets(RenderTargetConfig {
+            view: TargetView::Scene,
+        })
+        .outline(OutlineConfig {
+            apply: false,
+            stencil_reference: 1,
+            width: 0.01,
+            color: Color::from_rgb(255, 160, 0),
+        })
         .transform_matrixes(create_view_port_matrix(
             w_f32 * 0.125,
             h_f32 * 0.125,
@@ -108,6 +205,12 @@ pub fn open_render_window(
     )
     .expect("Unable to open Window");
 
+    let mut rasterizer = TiledRasterizer::new(
+        render_config.rasterization.threads,
+        render_config.rasterization.tile_size,
+        render_config.rasterization.use_simd,
+    );
+
     let mut spin_light = false;
     let mut mouse_pressed = false;
     let mut mouse_down_pos = Vec2::ZERO;
@@ -123,7 +226,7 @@ pub fn open_render_window(
             spin_light = !spin_light;
         }
 
-        match handle_image_save_controls(&window, &draw_buffer) {
+        match handle_image_save_controls(&window, &output_buffer) {
             Ok(save_triggered) => {
                 if save_triggered {
                     println!("Successfully saved image")
@@ -132,6 +235,13 @@ pub fn open_render_window(
             Err(e) => println!("Error saving file: {}", e),
         };
         handle_render_config_controls(&window, &mut render_config);
+        rasterizer.set_use_simd(render_config.rasterization.use_simd);
+        rasterizer.set_occlusion_culling(render_config.rasterization.occlusion_culling);
+        rasterizer.set_depth_pre_pass(render_config.rasterization.depth_pre_pass);
+        if draw_buffer.get_sample_count() != render_config.rasterization.msaa {
+            draw_buffer.set_sample_count(render_config.rasterization.msaa);
+        }
+        draw_buffer.set_stencil_enabled(render_config.outline.apply);
         handle_camera_controls(
             &window,
             &mut render_config,
@@ -148,90 +258,211 @@ pub fn open_render_window(
             _ => (),
         }
 
-        draw_buffer.get_z_buffer_mut().clean_with(&f32::MIN);
-        draw_buffer.clean();
-
-        for model in render_config.models.iter() {
-            let TransformMatrixes {
-                view_matrix,
-                projection,
-                viewport_matrix,
-            } = render_config.transform_matrixes;
-
-            let mut lights = render_config.lights.clone();
-            if model.use_self_shadowing {
-                for light in lights.iter_mut() {
-                    match &mut light.kind {
-                        LightSourceKind::Linear {
-                            dir,
-                            shadow_buffer: local_z_buffer,
-                            transform_matrix,
-                        } => {
-                            if local_z_buffer.is_none() {
-                                *local_z_buffer = Some(PlaneBuffer::<f32>::new(
-                                    draw_buffer.get_z_buffer().get_width(),
-                                    draw_buffer.get_z_buffer().get_height(),
-                                    PlaneBufferCreateOption::Fill(|_| f32::MIN),
-                                ));
-                            }
-                            let light_rotation_matrix =
-                                Mat4::from_quat(Quat::from_rotation_arc((*dir).into(), Vec3::Z));
-
-                            let z_buffer = local_z_buffer.as_mut().unwrap();
-                            render_wavefront_depth(
-                                &model,
-                                z_buffer,
-                                viewport_matrix,
-                                projection,
-                                light_rotation_matrix,
-                                view_matrix,
-                            );
-
-                            let light_matrix = viewport_matrix
-                                * projection
-                                * model.model_matrix
-                                * light_rotation_matrix
-                                * view_matrix;
-
-                            let cam_matrix = viewport_matrix
-                                * projection
-                                * model.model_matrix
-                                * rotation_matrix
-                                * view_matrix;
-
-                            let cam_to_matrix = light_matrix * (cam_matrix.inverse());
-                            *transform_matrix = Some(cam_to_matrix);
-                        }
-                        _ => (),
-                    }
+        let clear_depth = get_clear_depth(&render_config.models);
+        draw_buffer.set_clear_depth(clear_depth);
+        draw_buffer.clean_all();
+
+        // Every receiver is shadowed by all of the casters. Maps are kept in the scene lights,
+        // so the cube maps of the point lights are reused while nothing moves.
+        if render_config
+            .models
+            .iter()
+            .any(|model| model.receives_shadows)
+        {
+            render_shadow_maps(
+                &mut render_config.lights,
+                &render_config.models,
+                z_buffer_size,
+                render_config.transform_matrixes.projection,
+                render_config.transform_matrixes.view_matrix,
+                rotation_matrix,
+            );
+        }
+        let camera = render_config
+            .transform_matrixes
+            .with_rotation(rotation_matrix);
+        let lights = render_config.lights.clone();
+
+        let target_view = render_config.render_targets.view;
+        let shade_scene =
+            render_config.wireframe.mode != WireframeMode::Only && target_view == TargetView::Scene;
+
+        let deferred_shading = render_config.rasterization.deferred_shading && shade_scene;
+        if deferred_shading {
+            g_buffer.clean();
+            for model in render_config
+                .models
+                .iter()
+                .filter(|model| model.supports_deferred_shading())
+            {
+                render_wavefront_g_buffer(model, &mut g_buffer, &rasterizer, &camera);
+            }
+
+            let mut lights = lights.clone();
+            rotate_lights(&mut lights, rotation_matrix);
+            render_deferred_lighting(
+                &g_buffer,
+                &mut draw_buffer,
+                &lights,
+                get_projection_eye(camera.projection),
+            )
+            .unwrap();
+        }
+
+        // Depth of all the opaque meshes goes first, so that the shaded draws
+        // below shade only what stays visible in the whole scene
+        if rasterizer.uses_depth_pre_pass() && shade_scene {
+            for model in render_config
+                .models
+                .iter()
+                .filter(|model| matches!(model.render_mode, RenderMode::Mesh))
+                .filter(|model| !(deferred_shading && model.supports_deferred_shading()))
+            {
+                render_wavefront_depth_pre_pass(model, &mut draw_buffer, &rasterizer, &camera);
+            }
+        }
+
+        let draw_order = get_draw_order(&render_config.models, &camera);
+        let mut transparency_started = false;
+
+        for model in draw_order.iter().map(|&idx| &render_config.models[idx]) {
+            if !shade_scene {
+                break;
+            }
+
+            // Already lit from the G-buffer
+            if deferred_shading && model.supports_deferred_shading() {
+                continue;
+            }
+
+            if model.material.is_transparent() && !transparency_started {
+                transparency_started = true;
+                if render_config.transparency.order_independent {
+                    draw_buffer.begin_transparency_accumulation(z_buffer_size);
                 }
             }
 
+            if let RenderMode::Points(style) = &model.render_mode {
+                render_wavefront_points(model, &mut draw_buffer, style, &camera);
+                continue;
+            }
+
             render_wavefront_mesh(
                 &model,
                 &mut draw_buffer,
-                lights,
-                render_config.transform_matrixes.viewport_matrix,
-                render_config.transform_matrixes.projection,
-                render_config.transform_matrixes.view_matrix,
-                rotation_matrix,
+                &rasterizer,
+                lights.clone(),
+                &camera,
             );
         }
 
+        draw_buffer.composite_transparency();
+
+        // Outlines go around the stencil marked by the forward shaded meshes
+        let outline = render_config.outline;
+        if outline.apply && shade_scene {
+            for model in render_config
+                .models
+                .iter()
+                .filter(|model| matches!(model.render_mode, RenderMode::Mesh))
+                .filter(|model| !(deferred_shading && model.supports_deferred_shading()))
+            {
+                render_wavefront_outline(
+                    model,
+                    &mut draw_buffer,
+                    &rasterizer,
+                    &camera,
+                    outline.stencil_reference,
+                    outline.width,
+                    outline.color,
+                );
+            }
+        }
+
+        draw_buffer.resolve();
+
+        // Meshes are rendered into the lit color and shading normal targets at once,
+        // the latter replaces the scene
+        if target_view == TargetView::Normals {
+            framebuffer.set_clear_depth(clear_depth);
+            framebuffer.clean();
+            for model in render_config
+                .models
+                .iter()
+                .filter(|model| matches!(model.render_mode, RenderMode::Mesh))
+            {
+                render_wavefront_framebuffer(
+                    model,
+                    &mut framebuffer,
+                    &rasterizer,
+                    lights.clone(),
+                    &camera,
+                );
+            }
+            copy_normals_target(&framebuffer, &mut draw_buffer);
+        }
+
         if render_config.ambient_occlusion.apply {
             render_ambient_occlusion(
                 &mut draw_buffer,
                 z_buffer_size,
-                render_config.ambient_occlusion.effect_radius,
+                render_config.ambient_occlusion.effect_radius * supersampling as f32,
                 render_config.ambient_occlusion.intensity,
             );
         }
 
+        if render_config.wireframe.mode != WireframeMode::Off {
+            let wireframe = render_config.wireframe;
+
+            // Without shaded models the z-buffer has to be filled just for the lines
+            if wireframe.mode == WireframeMode::Only && wireframe.hidden_line_removal {
+                for model in render_config.models.iter() {
+                    render_wavefront_depth(
+                        model,
+                        draw_buffer.get_z_buffer_mut(),
+                        &camera,
+                        &model.depth,
+                    );
+                }
+            }
+
+            for model in render_config.models.iter() {
+                render_wavefront_grid(
+                    model,
+                    &mut draw_buffer,
+                    &camera,
+                    &wireframe.line_style,
+                    if wireframe.hidden_line_removal {
+                        Some(wireframe.depth_offset)
+                    } else {
+                        None
+                    },
+                );
+            }
+        }
+
+        if supersampling > 1 {
+            downsample(
+                &draw_buffer,
+                &mut output_buffer,
+                render_config.anti_aliasing.downsample_filter,
+            )
+            .unwrap();
+        } else {
+            output_buffer
+                .get_buffer_mut()
+                .copy_from_slice(draw_buffer.get_buffer());
+        }
+
+        if render_config.anti_aliasing.fxaa {
+            apply_fxaa(&mut output_buffer);
+        }
+
         window
             .update_with_buffer(
-                draw_buffer.get_buffer_as_u32_ref(),
-                draw_buffer.get_width(),
-                draw_buffer.get_height(),
+                output_buffer.get_buffer_as_u32_ref(),
+                output_buffer.get_width(),
+                output_buffer.get_height(),
             )
             .unwrap();
 
@@ -240,8 +471,18 @@ pub fn open_render_window(
         t_delta = (end - start).as_secs_f32();
 
         window.set_title(&format!(
-            "Renderust {:1.1?} FPS, [R] light {}, yaw: {:1.2}, pitch: {:1.2}",
+            "Renderust {:1.1?} FPS ({} threads, {}{}{}{}, MSAA x{}, SSAA x{} {:?}{}{}), [R] light {}, yaw: {:1.2}, pitch: {:1.2}",
             1.0 / t_delta,
+            rasterizer.get_threads_count(),
+            if rasterizer.uses_simd() { "SIMD" } else { "scalar" },
+            if rasterizer.uses_occlusion_culling() { ", Hi-Z" } else { "" },
+            if rasterizer.uses_depth_pre_pass() { ", Z pre-pass" } else { "" },
+            if deferred_shading { ", deferred" } else { "" },
+            draw_buffer.get_sample_count().get_count(),
+            supersampling,
+            render_config.anti_aliasing.downsample_filter,
+            if render_config.anti_aliasing.fxaa { ", FXAA" } else { "" },
+            if render_config.transparency.order_independent { ", OIT" } else { "" },
             if spin_light { "spinning" } else { "fixed" },
             render_config.camera.yaw,
             render_config.camera.pitch
@@ -252,3 +493,55 @@ pub fn open_render_window(
         }
     }
 }
+
+// Shading normals of the covered pixels, mapped from -1..1 into RGB
+fn copy_normals_target(framebuffer: &Framebuffer, canvas: &mut DrawingBuffer) {
+    let normals = framebuffer
+        .get_target(NORMAL_TARGET)
+        .and_then(RenderTarget::get_vec3a_plane)
+        .expect("Framebuffer has no normal target");
+    let depths = framebuffer.get_depth_buffer().get_buffer();
+    let clear_depth = framebuffer.get_clear_depth();
+
+    for ((color, normal), z_depth) in canvas
+        .get_buffer_mut()
+        .iter_mut()
+        .zip(normals.get_buffer())
+        .zip(depths)
+    {
+        if *z_depth != clear_depth {
+            *color = Color::from_vec4(Vec4::from((normal.normalize_or_zero() * 0.5 + 0.5, 1.0)));
+        }
+    }
+}
+
+/// Depth which every model writing depth passes its depth test against.
+/// A single buffer serves all of the models, so they have to agree on the compare direction.
+fn get_clear_depth(models: &[WavefrontRenderModel]) -> f32 {
+    let mut clear_depths = models
+        .iter()
+        .filter(|model| model.depth.write)
+        .map(|model| model.depth.get_clear_depth());
+    let clear_depth = clear_depths.next().unwrap_or(f32::MIN);
+    assert!(
+        clear_depths.all(|depth| depth == clear_depth),
+        "Models writing depth mix the Less and Greater compare directions"
+    );
+    clear_depth
+}
+
+/// Opaque models go first in their original order,
+/// followed by the transparent ones sorted back to front
+fn get_draw_order(models: &[WavefrontRenderModel], camera: &CameraMatrixes) -> Vec<usize> {
+    let (transparent, mut order): (Vec<usize>, Vec<usize>) =
+        (0..models.len()).partition(|&idx| models[idx].material.is_transparent());
+
+    let mut transparent: Vec<(usize, f32)> = transparent
+        .into_iter()
+        .map(|idx| (idx, calculate_model_depth(&models[idx], camera)))
+        .collect();
+    transparent.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    order.extend(transparent.into_iter().map(|(idx, _)| idx));
+    order
+}
@@ -1,6 +1,15 @@
+use glam::Vec3A;
 use minifb::{Key, KeyRepeat, Window};
 
-use crate::ui::render_window::render_config::render_config::RenderConfig;
+use crate::{
+    ui::render_window::render_config::render_config::RenderConfig,
+    visual::rendering::{
+        light_source::{Attenuation, LightSource, LightSourceKind, SpotCone},
+        shadow::shadow_settings::{CubeShadowSettings, ShadowSettings},
+        stencil::stencil_state::StencilState,
+        wavefront_obj::wavefront_render_model::RenderMode,
+    },
+};
 
 pub fn handle_render_config_controls(window: &Window, render_config: &mut RenderConfig) {
     // Normal map toggle
@@ -24,10 +33,10 @@ pub fn handle_render_config_controls(window: &Window, render_config: &mut Render
         }
     }
 
-    // Self-shadow toggle
+    // Shadow receiving toggle
     if window.is_key_pressed(Key::Key4, KeyRepeat::No) {
         for model in render_config.models.iter_mut() {
-            model.use_self_shadowing = !model.use_self_shadowing;
+            model.receives_shadows = !model.receives_shadows;
         }
     }
 
@@ -35,4 +44,242 @@ pub fn handle_render_config_controls(window: &Window, render_config: &mut Render
     if window.is_key_pressed(Key::Key5, KeyRepeat::No) {
         render_config.ambient_occlusion.apply = !render_config.ambient_occlusion.apply;
     }
+
+    // SIMD rasterization toggle
+    if window.is_key_pressed(Key::Key6, KeyRepeat::No) {
+        render_config.rasterization.use_simd = !render_config.rasterization.use_simd;
+    }
+
+    // Hierarchical z-buffer occlusion culling toggle
+    if window.is_key_pressed(Key::Q, KeyRepeat::No) {
+        render_config.rasterization.occlusion_culling =
+            !render_config.rasterization.occlusion_culling;
+    }
+
+    // Depth pre-pass toggle
+    if window.is_key_pressed(Key::Z, KeyRepeat::No) {
+        render_config.rasterization.depth_pre_pass = !render_config.rasterization.depth_pre_pass;
+    }
+
+    // Deferred shading toggle
+    if window.is_key_pressed(Key::E, KeyRepeat::No) {
+        render_config.rasterization.deferred_shading =
+            !render_config.rasterization.deferred_shading;
+    }
+
+    // MSAA samples count switch
+    if window.is_key_pressed(Key::Key7, KeyRepeat::No) {
+        render_config.rasterization.msaa = render_config.rasterization.msaa.next();
+    }
+
+    // FXAA toggle
+    if window.is_key_pressed(Key::Key8, KeyRepeat::No) {
+        render_config.anti_aliasing.fxaa = !render_config.anti_aliasing.fxaa;
+    }
+
+    // Supersampling downsample filter switch
+    if window.is_key_pressed(Key::Key9, KeyRepeat::No) {
+        render_config.anti_aliasing.downsample_filter =
+            render_config.anti_aliasing.downsample_filter.next();
+    }
+
+    // Wireframe mode switch
+    if window.is_key_pressed(Key::Key0, KeyRepeat::No) {
+        render_config.wireframe.mode = render_config.wireframe.mode.next();
+    }
+
+    // Hidden line removal toggle
+    if window.is_key_pressed(Key::H, KeyRepeat::No) {
+        render_config.wireframe.hidden_line_removal = !render_config.wireframe.hidden_line_removal;
+    }
+
+    // Stencil outline toggle
+    if window.is_key_pressed(Key::F1, KeyRepeat::No) {
+        let outline = &mut render_config.outline;
+        outline.apply = !outline.apply;
+        for model in render_config.models.iter_mut() {
+            if let RenderMode::Mesh = model.render_mode {
+                model.stencil = outline
+                    .apply
+                    .then(|| StencilState::write(outline.stencil_reference));
+            }
+        }
+    }
+
+    // Displayed render target switch
+    if window.is_key_pressed(Key::F2, KeyRepeat::No) {
+        render_config.render_targets.view = render_config.render_targets.view.next();
+    }
+
+    // Point shape switch
+    if window.is_key_pressed(Key::P, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            if let RenderMode::Points(style) = &mut model.render_mode {
+                style.shape = style.shape.next();
+            }
+        }
+    }
+
+    // Point coloring switch
+    if window.is_key_pressed(Key::C, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            if let RenderMode::Points(style) = &mut model.render_mode {
+                style.coloring = style.coloring.next();
+            }
+        }
+    }
+
+    // Mesh shader switch
+    if window.is_key_pressed(Key::G, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.shader = model.shader.next();
+        }
+    }
+
+    // Texture filter switch
+    if window.is_key_pressed(Key::F, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.sampler.filter = model.material.sampler.filter.next();
+        }
+    }
+
+    // Mipmaps toggle
+    if window.is_key_pressed(Key::M, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.sampler.use_mipmaps = !model.material.sampler.use_mipmaps;
+        }
+    }
+
+    // Anisotropic filtering switch
+    if window.is_key_pressed(Key::X, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.sampler.max_anisotropy = model.material.sampler.next_anisotropy();
+        }
+    }
+
+    // Texture wrap mode switch
+    if window.is_key_pressed(Key::U, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.sampler.wrap = model.material.sampler.wrap.next();
+        }
+    }
+
+    // Blend mode switch
+    if window.is_key_pressed(Key::B, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.blend_mode = model.material.blend_mode.next();
+        }
+    }
+
+    // Opacity switch (100%/75%/50%/25%)
+    if window.is_key_pressed(Key::O, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.opacity = if model.material.opacity <= 0.25 {
+                1.0
+            } else {
+                model.material.opacity - 0.25
+            };
+        }
+    }
+
+    // Alpha cutout toggle
+    if window.is_key_pressed(Key::K, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.alpha_cutoff = match model.material.alpha_cutoff {
+                Some(_) => None,
+                None => Some(0.5),
+            };
+        }
+    }
+
+    // Shading model switch
+    if window.is_key_pressed(Key::I, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.shading_model = model.material.shading_model.next();
+        }
+    }
+
+    // Specular model switch
+    if window.is_key_pressed(Key::V, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.specular.model = model.material.specular.model.next();
+        }
+    }
+
+    // Specular energy conservation toggle
+    if window.is_key_pressed(Key::N, KeyRepeat::No) {
+        for model in render_config.models.iter_mut() {
+            model.material.specular.energy_conservation =
+                !model.material.specular.energy_conservation;
+        }
+    }
+
+    // Shadow filter switch
+    if window.is_key_pressed(Key::J, KeyRepeat::No) {
+        for light in render_config.lights.iter_mut() {
+            match &mut light.kind {
+                LightSourceKind::Linear { shadow, .. } | LightSourceKind::Spot { shadow, .. } => {
+                    shadow.filter = shadow.filter.next();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Cascaded shadow maps of the directional lights
+    if window.is_key_pressed(Key::Y, KeyRepeat::No) {
+        for light in render_config.lights.iter_mut() {
+            if let LightSourceKind::Linear { shadow, .. } = &mut light.kind {
+                shadow.cascades = if shadow.cascades > 1 { 1 } else { 4 };
+            }
+        }
+    }
+
+    // Local light cycle: none, point, spot
+    if window.is_key_pressed(Key::L, KeyRepeat::No) {
+        let local_light = render_config.lights.iter().position(|light| {
+            matches!(
+                light.kind,
+                LightSourceKind::Point { .. } | LightSourceKind::Spot { .. }
+            )
+        });
+        match local_light {
+            Some(idx) => {
+                let light = render_config.lights.remove(idx);
+                if let LightSourceKind::Point { .. } = light.kind {
+                    render_config.lights.push(LightSource::new(
+                        LightSourceKind::Spot {
+                            position: Vec3A::new(0.0, 2.0, 2.0),
+                            dir: Vec3A::new(0.0, -1.0, -1.0).normalize(),
+                            cone: SpotCone {
+                                inner_angle: 15f32.to_radians(),
+                                outer_angle: 25f32.to_radians(),
+                            },
+                            attenuation: Attenuation::InverseSquare { radius: 6.0 },
+                            shadow: ShadowSettings::default(),
+                            shadow_map: None,
+                        },
+                        Vec3A::new(1.0, 0.9, 0.7) * 6.0,
+                        1.0,
+                    ));
+                }
+            }
+            None => render_config.lights.push(LightSource::new(
+                LightSourceKind::Point {
+                    position: Vec3A::new(-0.5, 0.5, 1.0),
+                    attenuation: Attenuation::InverseSquare { radius: 3.0 },
+                    shadow: CubeShadowSettings::default(),
+                    shadow_map: None,
+                },
+                Vec3A::new(1.0, 0.7, 0.4) * 1.5,
+                1.0,
+            )),
+        }
+    }
+
+    // Order-independent transparency toggle
+    if window.is_key_pressed(Key::T, KeyRepeat::No) {
+        render_config.transparency.order_independent =
+            !render_config.transparency.order_independent;
+    }
 }
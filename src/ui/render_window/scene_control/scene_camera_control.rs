@@ -4,7 +4,7 @@ use minifb::{Key, MouseButton, MouseMode, Window};
 use crate::{
     math::rotation::create_rotation_matrix,
     ui::render_window::render_config::render_config::{CameraConfig, RenderConfig},
-    visual::rendering::matrix::projection_matrix::create_projection_matrix,
+    visual::rendering::matrix::projection_matrix::create_reversed_z_projection_matrix,
 };
 
 const MOVE_SPEED: f32 = 2.0;
@@ -29,10 +29,11 @@ pub fn handle_camera_controls(
 ) {
     if let Some((_, y)) = window.get_scroll_wheel() {
         let diff = -y / 100.0;
-        let new_distance = (render_config.camera.distance + diff).max(0.85);
-        render_config.camera.distance = new_distance;
+        let camera = &mut render_config.camera;
+        camera.distance = (camera.distance + diff).max(0.85);
 
-        render_config.transform_matrixes.projection = create_projection_matrix(new_distance);
+        render_config.transform_matrixes.projection =
+            create_reversed_z_projection_matrix(camera.distance, camera.near, camera.far);
     }
 
     let (x, y) = window.get_mouse_pos(MouseMode::Pass).unwrap();
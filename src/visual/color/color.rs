@@ -1,6 +1,6 @@
 use std::ops::Mul;
 
-use glam::Vec3A;
+use glam::{Vec3A, Vec4};
 use image::Rgba;
 use num::{Num, NumCast};
 use rand::prelude::*;
@@ -36,6 +36,22 @@ impl Color {
         Color { b, g, r, alpha }
     }
 
+    /// Color from RGBA channels in 0..1 range
+    pub fn from_vec4(rgba: Vec4) -> Color {
+        let rgba = (rgba * 255.0).round().clamp(Vec4::ZERO, Vec4::splat(255.0));
+        Color::from_rgb_with_alpha(rgba.x as u8, rgba.y as u8, rgba.z as u8, rgba.w as u8)
+    }
+
+    /// RGBA channels in 0..1 range
+    pub fn to_vec4(self) -> Vec4 {
+        Vec4::new(
+            self.r as f32,
+            self.g as f32,
+            self.b as f32,
+            self.alpha as f32,
+        ) / 255.0
+    }
+
     pub fn from_hsv(h: u16, s: f32, v: f32) -> Color {
         let c = v * s as f32;
         let x = c * (1.0 - ((h as f32 / 60.0) % 2.0 - 1.0).abs()) as f32;
@@ -78,6 +94,64 @@ impl Color {
         let b = (self.b as f32 * intensities.z) as u8;
         Color::from_rgb_with_alpha(r, g, b, self.alpha)
     }
+
+    /// Adds light which isn't filtered by the color, e.g. specular highlights
+    pub fn add_intensity(self, intensities: Vec3A) -> Color {
+        let add_channel = |c: u8, intensity: f32| (c as f32 + intensity * 255.0) as u8;
+        Color::from_rgb_with_alpha(
+            add_channel(self.r, intensities.x),
+            add_channel(self.g, intensities.y),
+            add_channel(self.b, intensities.z),
+            self.alpha,
+        )
+    }
+
+    /// Linear RGB of the sRGB encoded color, alpha is kept
+    pub fn to_linear(self) -> Vec4 {
+        let decode = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Vec4::new(
+            decode(self.r),
+            decode(self.g),
+            decode(self.b),
+            self.alpha as f32 / 255.0,
+        )
+    }
+
+    /// Encodes the linear RGB into sRGB, values above one are clamped
+    pub fn from_linear(rgba: Vec4) -> Color {
+        let encode = |c: f32| {
+            let c = c.clamp(0.0, 1.0);
+            let c = if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (c * 255.0).round() as u8
+        };
+        Color::from_rgb_with_alpha(
+            encode(rgba.x),
+            encode(rgba.y),
+            encode(rgba.z),
+            (rgba.w.clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let lerp_channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::from_rgb_with_alpha(
+            lerp_channel(self.r, other.r),
+            lerp_channel(self.g, other.g),
+            lerp_channel(self.b, other.b),
+            lerp_channel(self.alpha, other.alpha),
+        )
+    }
 }
 
 impl From<Rgba<u8>> for Color {
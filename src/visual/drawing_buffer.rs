@@ -1,21 +1,45 @@
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 
-use crate::plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption};
+use crate::{
+    math::geometry::rect::Rect,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+};
 
-use super::color::color::Color;
+use super::{
+    color::color::Color,
+    multisampling::SampleCount,
+    rendering::{
+        stencil::stencil_state::{StencilOp, StencilState},
+        tiling::tile_target::TileTarget,
+        transparency::weighted_oit::OitBuffers,
+    },
+};
 
-pub struct DrawingBuffer(PlaneBuffer<Color>, PlaneBuffer<f32>);
+pub struct DrawingBuffer {
+    color_buffer: PlaneBuffer<Color>,
+    z_buffer: PlaneBuffer<f32>,
+    screen_rect: Rect,
+    sample_count: SampleCount,
+    // Per-sample color and depth planes, empty without multisampling
+    sample_buffers: Vec<(PlaneBuffer<Color>, PlaneBuffer<f32>)>,
+    // Present only while order-independent transparency is being accumulated
+    oit_buffers: Option<OitBuffers>,
+    // Per-pixel stencil values, shared by the samples
+    stencil_buffer: Option<PlaneBuffer<u8>>,
+    depth_clip_range: Range<f32>,
+    clear_depth: f32,
+}
 
 impl Deref for DrawingBuffer {
     type Target = PlaneBuffer<Color>;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.color_buffer
     }
 }
 
 impl DerefMut for DrawingBuffer {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.color_buffer
     }
 }
 
@@ -25,17 +49,291 @@ impl DrawingBuffer {
         height: usize,
         create_option: PlaneBufferCreateOption<Color>,
     ) -> DrawingBuffer {
-        DrawingBuffer(
-            PlaneBuffer::new(width, height, create_option),
-            PlaneBuffer::new(width, height, PlaneBufferCreateOption::Fill(|_| f32::MIN)),
-        )
+        DrawingBuffer {
+            color_buffer: PlaneBuffer::new(width, height, create_option),
+            z_buffer: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Fill(|_| f32::MIN)),
+            screen_rect: Rect::new(0, 0, width, height),
+            sample_count: SampleCount::X1,
+            sample_buffers: Vec::new(),
+            oit_buffers: None,
+            stencil_buffer: None,
+            depth_clip_range: f32::MIN..f32::MAX,
+            clear_depth: f32::MIN,
+        }
     }
 
     pub fn get_z_buffer(&self) -> &PlaneBuffer<f32> {
-        &self.1
+        &self.z_buffer
     }
 
     pub fn get_z_buffer_mut(&mut self) -> &mut PlaneBuffer<f32> {
-        &mut self.1
+        &mut self.z_buffer
+    }
+
+    /// Area of the screen covered by this buffer.
+    /// Differs from `(0, 0, width, height)` only for tiles.
+    pub fn get_screen_rect(&self) -> Rect {
+        self.screen_rect
+    }
+
+    pub fn get_sample_count(&self) -> SampleCount {
+        self.sample_count
+    }
+
+    pub fn set_sample_count(&mut self, sample_count: SampleCount) {
+        let (width, height) = (self.get_width(), self.get_height());
+        let clear_depth = self.clear_depth;
+        self.sample_count = sample_count;
+        self.sample_buffers = if sample_count == SampleCount::X1 {
+            Vec::new()
+        } else {
+            (0..sample_count.get_count())
+                .map(|_| {
+                    let mut z_buffer =
+                        PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank);
+                    z_buffer.clean_with(&clear_depth);
+                    (
+                        PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+                        z_buffer,
+                    )
+                })
+                .collect()
+        };
+    }
+
+    /// Faces with vertices outside of the range are not drawn,
+    /// e.g. those crossing the near and far planes of the projection.
+    /// Unbounded by default.
+    pub fn get_depth_clip_range(&self) -> Range<f32> {
+        self.depth_clip_range.clone()
+    }
+
+    pub fn set_depth_clip_range(&mut self, depth_clip_range: Range<f32>) {
+        self.depth_clip_range = depth_clip_range;
+    }
+
+    /// Depth the planes are cleared to by `clean_all`, `f32::MIN` by default
+    /// as larger depth is closer to the viewer with the default depth test.
+    /// See `DepthState::get_clear_depth`.
+    pub fn get_clear_depth(&self) -> f32 {
+        self.clear_depth
+    }
+
+    pub fn set_clear_depth(&mut self, clear_depth: f32) {
+        self.clear_depth = clear_depth;
+    }
+
+    /// Color and depth planes of the sample.
+    /// Without multisampling the only sample is stored in the main planes.
+    pub fn get_sample_buffers(&self, sample: usize) -> (&PlaneBuffer<Color>, &PlaneBuffer<f32>) {
+        match self.sample_buffers.get(sample) {
+            Some((color_buffer, z_buffer)) => (color_buffer, z_buffer),
+            None => (&self.color_buffer, &self.z_buffer),
+        }
+    }
+
+    pub fn get_sample_buffers_mut(
+        &mut self,
+        sample: usize,
+    ) -> (&mut PlaneBuffer<Color>, &mut PlaneBuffer<f32>) {
+        match self.sample_buffers.get_mut(sample) {
+            Some((color_buffer, z_buffer)) => (color_buffer, z_buffer),
+            None => (&mut self.color_buffer, &mut self.z_buffer),
+        }
+    }
+
+    /// Resets color, depth and stencil of the pixels along with all of the samples
+    pub fn clean_all(&mut self) {
+        self.color_buffer.clean();
+        self.z_buffer.clean_with(&self.clear_depth);
+        for (color_buffer, z_buffer) in self.sample_buffers.iter_mut() {
+            color_buffer.clean();
+            z_buffer.clean_with(&self.clear_depth);
+        }
+        if let Some(stencil_buffer) = &mut self.stencil_buffer {
+            stencil_buffer.clean();
+        }
+    }
+
+    /// Adds a zero filled 8-bit stencil plane or removes it.
+    /// Without the plane stencil states of the draws are ignored.
+    pub fn set_stencil_enabled(&mut self, enabled: bool) {
+        self.stencil_buffer = match (enabled, self.stencil_buffer.take()) {
+            (false, _) => None,
+            (true, Some(stencil_buffer)) => Some(stencil_buffer),
+            (true, None) => Some(PlaneBuffer::new(
+                self.get_width(),
+                self.get_height(),
+                PlaneBufferCreateOption::Blank,
+            )),
+        };
+    }
+
+    pub fn get_stencil_buffer(&self) -> Option<&PlaneBuffer<u8>> {
+        self.stencil_buffer.as_ref()
+    }
+
+    pub fn get_stencil_buffer_mut(&mut self) -> Option<&mut PlaneBuffer<u8>> {
+        self.stencil_buffer.as_mut()
+    }
+
+    /// Stencil test of the pixel, failing it applies the fail operation.
+    /// Always passes without the stencil plane.
+    pub fn stencil_test(&mut self, p: (usize, usize), stencil: &StencilState) -> bool {
+        let stencil_buffer = match &mut self.stencil_buffer {
+            Some(stencil_buffer) => stencil_buffer,
+            None => return true,
+        };
+        let stored = &mut stencil_buffer[p];
+        if stencil.passes(*stored) {
+            true
+        } else {
+            stencil.update(stored, stencil.fail_op);
+            false
+        }
+    }
+
+    pub fn stencil_update(&mut self, p: (usize, usize), stencil: &StencilState, op: StencilOp) {
+        if let Some(stencil_buffer) = &mut self.stencil_buffer {
+            stencil.update(&mut stencil_buffer[p], op);
+        }
+    }
+
+    /// Averages sample colors into the pixel color and keeps the nearest sample depth,
+    /// the one farthest from the clear depth
+    pub fn resolve(&mut self) {
+        if self.sample_buffers.is_empty() {
+            return;
+        }
+        let samples_count = self.sample_buffers.len() as u32;
+        let nearest = if self.clear_depth == f32::MAX {
+            f32::min
+        } else {
+            f32::max
+        };
+
+        for idx in 0..self.color_buffer.get_buffer().len() {
+            let (mut b, mut g, mut r, mut alpha) = (0u32, 0u32, 0u32, 0u32);
+            let mut z_depth = self.clear_depth;
+            for (color_buffer, z_buffer) in self.sample_buffers.iter() {
+                let color = color_buffer.get_buffer()[idx];
+                b += color.b as u32;
+                g += color.g as u32;
+                r += color.r as u32;
+                alpha += color.alpha as u32;
+                z_depth = nearest(z_depth, z_buffer.get_buffer()[idx]);
+            }
+            self.color_buffer.get_buffer_mut()[idx] = Color::from_rgb_with_alpha(
+                (r / samples_count) as u8,
+                (g / samples_count) as u8,
+                (b / samples_count) as u8,
+                (alpha / samples_count) as u8,
+            );
+            self.z_buffer.get_buffer_mut()[idx] = z_depth;
+        }
+    }
+
+    /// Alpha blended fragments are accumulated into separate planes
+    /// until `composite_transparency` is called
+    pub fn begin_transparency_accumulation(&mut self, depth_range: f32) {
+        self.oit_buffers = Some(OitBuffers::new(
+            self.get_width(),
+            self.get_height(),
+            depth_range,
+        ));
+    }
+
+    pub fn is_accumulating_transparency(&self) -> bool {
+        self.oit_buffers.is_some()
+    }
+
+    pub fn accumulate_transparent(&mut self, p: (usize, usize), color: Color, z_depth: f32) {
+        if let Some(oit_buffers) = &mut self.oit_buffers {
+            oit_buffers.accumulate(p, color, z_depth);
+        }
+    }
+
+    /// Blends accumulated transparency over the pixels and all of the samples
+    pub fn composite_transparency(&mut self) {
+        let oit_buffers = match self.oit_buffers.take() {
+            Some(oit_buffers) => oit_buffers,
+            None => return,
+        };
+        oit_buffers.composite(&mut self.color_buffer);
+        for (color_buffer, _) in self.sample_buffers.iter_mut() {
+            oit_buffers.composite(color_buffer);
+        }
+    }
+
+    fn to_local_rect(&self, rect: &Rect) -> Rect {
+        Rect {
+            x: rect.x - self.screen_rect.x,
+            y: rect.y - self.screen_rect.y,
+            size: rect.size,
+        }
+    }
+}
+
+impl TileTarget for DrawingBuffer {
+    fn get_screen_rect(&self) -> Rect {
+        self.screen_rect
+    }
+
+    fn get_sample_radius(&self) -> f32 {
+        self.sample_count.get_max_offset()
+    }
+
+    fn copy_tile(&self, tile: &Rect) -> DrawingBuffer {
+        let local_tile = self.to_local_rect(tile);
+        DrawingBuffer {
+            color_buffer: self.color_buffer.copy_region(&local_tile),
+            z_buffer: self.z_buffer.copy_region(&local_tile),
+            screen_rect: *tile,
+            sample_count: self.sample_count,
+            sample_buffers: self
+                .sample_buffers
+                .iter()
+                .map(|(color_buffer, z_buffer)| {
+                    (
+                        color_buffer.copy_region(&local_tile),
+                        z_buffer.copy_region(&local_tile),
+                    )
+                })
+                .collect(),
+            oit_buffers: self
+                .oit_buffers
+                .as_ref()
+                .map(|oit_buffers| oit_buffers.copy_region(&local_tile)),
+            stencil_buffer: self
+                .stencil_buffer
+                .as_ref()
+                .map(|stencil_buffer| stencil_buffer.copy_region(&local_tile)),
+            depth_clip_range: self.depth_clip_range.clone(),
+            clear_depth: self.clear_depth,
+        }
+    }
+
+    fn paste_tile(&mut self, tile: &DrawingBuffer) {
+        let local_tile = self.to_local_rect(&tile.screen_rect);
+        self.color_buffer.paste_region(&local_tile, &tile.color_buffer);
+        self.z_buffer.paste_region(&local_tile, &tile.z_buffer);
+        for ((color_buffer, z_buffer), (tile_color_buffer, tile_z_buffer)) in self
+            .sample_buffers
+            .iter_mut()
+            .zip(tile.sample_buffers.iter())
+        {
+            color_buffer.paste_region(&local_tile, tile_color_buffer);
+            z_buffer.paste_region(&local_tile, tile_z_buffer);
+        }
+        if let (Some(oit_buffers), Some(tile_oit_buffers)) =
+            (&mut self.oit_buffers, &tile.oit_buffers)
+        {
+            oit_buffers.paste_region(&local_tile, tile_oit_buffers);
+        }
+        if let (Some(stencil_buffer), Some(tile_stencil_buffer)) =
+            (&mut self.stencil_buffer, &tile.stencil_buffer)
+        {
+            stencil_buffer.paste_region(&local_tile, tile_stencil_buffer);
+        }
     }
 }
@@ -1,4 +1,5 @@
 pub mod drawing_buffer;
+pub mod multisampling;
 pub mod color {
     pub mod color;
 }
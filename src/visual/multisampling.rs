@@ -0,0 +1,64 @@
+use glam::Vec2;
+
+// Standard D3D sample patterns (offsets from pixel center, defined on a 1/16 pixel grid)
+// with y flipped, since screen y points up
+const POSITIONS_X1: [Vec2; 1] = [Vec2::ZERO];
+const POSITIONS_X2: [Vec2; 2] = [Vec2::new(0.25, -0.25), Vec2::new(-0.25, 0.25)];
+const POSITIONS_X4: [Vec2; 4] = [
+    Vec2::new(-2.0 / 16.0, 6.0 / 16.0),
+    Vec2::new(6.0 / 16.0, 2.0 / 16.0),
+    Vec2::new(-6.0 / 16.0, -2.0 / 16.0),
+    Vec2::new(2.0 / 16.0, -6.0 / 16.0),
+];
+const POSITIONS_X8: [Vec2; 8] = [
+    Vec2::new(1.0 / 16.0, 3.0 / 16.0),
+    Vec2::new(-1.0 / 16.0, -3.0 / 16.0),
+    Vec2::new(5.0 / 16.0, -1.0 / 16.0),
+    Vec2::new(-3.0 / 16.0, 5.0 / 16.0),
+    Vec2::new(-5.0 / 16.0, -5.0 / 16.0),
+    Vec2::new(-7.0 / 16.0, 1.0 / 16.0),
+    Vec2::new(3.0 / 16.0, -7.0 / 16.0),
+    Vec2::new(7.0 / 16.0, 7.0 / 16.0),
+];
+
+pub const MAX_SAMPLES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleCount {
+    X1,
+    X2,
+    X4,
+    X8,
+}
+
+impl SampleCount {
+    pub fn get_count(&self) -> usize {
+        self.get_positions().len()
+    }
+
+    pub fn get_positions(&self) -> &'static [Vec2] {
+        match self {
+            SampleCount::X1 => &POSITIONS_X1,
+            SampleCount::X2 => &POSITIONS_X2,
+            SampleCount::X4 => &POSITIONS_X4,
+            SampleCount::X8 => &POSITIONS_X8,
+        }
+    }
+
+    /// Largest distance of a sample from pixel center along either axis
+    pub fn get_max_offset(&self) -> f32 {
+        self.get_positions()
+            .iter()
+            .map(|p| p.abs().max_element())
+            .fold(0.0, f32::max)
+    }
+
+    pub fn next(&self) -> SampleCount {
+        match self {
+            SampleCount::X1 => SampleCount::X2,
+            SampleCount::X2 => SampleCount::X4,
+            SampleCount::X4 => SampleCount::X8,
+            SampleCount::X8 => SampleCount::X1,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use glam::{Vec2, Vec3A};
+
+use crate::{plane_buffer::plane_buffer::PlaneBuffer, visual::color::color::Color};
+
+// Minimal local contrast (relative to the brightest neighbour) treated as an edge
+const EDGE_THRESHOLD: f32 = 1.0 / 8.0;
+// Lets dark areas skip processing
+const EDGE_THRESHOLD_MIN: f32 = 1.0 / 32.0;
+// Amount of blending for single pixel wide details
+const SUBPIXEL_QUALITY: f32 = 0.75;
+// Lengths of the consecutive steps made while searching for the edge ends
+const SEARCH_STEPS: [f32; 12] = [1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0];
+
+struct LumaView<'a> {
+    lumas: &'a [f32],
+    width: usize,
+    height: usize,
+}
+
+impl LumaView<'_> {
+    fn get(&self, x: isize, y: isize) -> f32 {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.lumas[x + y * self.width]
+    }
+
+    fn sample(&self, p: Vec2) -> f32 {
+        bilinear(p, |x, y| self.get(x, y))
+    }
+}
+
+/// Fast approximate anti-aliasing over the final image.
+/// Finds edges by luma contrast and blends pixels across them.
+pub fn apply_fxaa(canvas: &mut PlaneBuffer<Color>) {
+    let (width, height) = (canvas.get_width(), canvas.get_height());
+    if width == 0 || height == 0 {
+        return;
+    }
+    let source = canvas.get_buffer().clone();
+    let lumas: Vec<f32> = source.iter().map(luma).collect();
+    let view = LumaView {
+        lumas: &lumas,
+        width,
+        height,
+    };
+    let get_color = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, height as isize - 1) as usize;
+        color_to_vec(source[x + y * width])
+    };
+
+    let output = canvas.get_buffer_mut();
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let luma_center = view.get(x, y);
+            let luma_down = view.get(x, y - 1);
+            let luma_up = view.get(x, y + 1);
+            let luma_left = view.get(x - 1, y);
+            let luma_right = view.get(x + 1, y);
+
+            let luma_min = luma_center
+                .min(luma_down)
+                .min(luma_up)
+                .min(luma_left)
+                .min(luma_right);
+            let luma_max = luma_center
+                .max(luma_down)
+                .max(luma_up)
+                .max(luma_left)
+                .max(luma_right);
+            let luma_range = luma_max - luma_min;
+            if luma_range < EDGE_THRESHOLD_MIN.max(luma_max * EDGE_THRESHOLD) {
+                continue;
+            }
+
+            let luma_down_left = view.get(x - 1, y - 1);
+            let luma_up_right = view.get(x + 1, y + 1);
+            let luma_up_left = view.get(x - 1, y + 1);
+            let luma_down_right = view.get(x + 1, y - 1);
+
+            let luma_down_up = luma_down + luma_up;
+            let luma_left_right = luma_left + luma_right;
+            let luma_left_corners = luma_down_left + luma_up_left;
+            let luma_down_corners = luma_down_left + luma_down_right;
+            let luma_right_corners = luma_down_right + luma_up_right;
+            let luma_up_corners = luma_up_right + luma_up_left;
+
+            let edge_horizontal = (-2.0 * luma_left + luma_left_corners).abs()
+                + (-2.0 * luma_center + luma_down_up).abs() * 2.0
+                + (-2.0 * luma_right + luma_right_corners).abs();
+            let edge_vertical = (-2.0 * luma_up + luma_up_corners).abs()
+                + (-2.0 * luma_center + luma_left_right).abs() * 2.0
+                + (-2.0 * luma_down + luma_down_corners).abs();
+            let is_horizontal = edge_horizontal >= edge_vertical;
+
+            // Neighbours on both sides of the edge
+            let (luma1, luma2) = if is_horizontal {
+                (luma_down, luma_up)
+            } else {
+                (luma_left, luma_right)
+            };
+            let gradient1 = luma1 - luma_center;
+            let gradient2 = luma2 - luma_center;
+            let gradient_scaled = 0.25 * gradient1.abs().max(gradient2.abs());
+
+            // Step towards the side with the steepest gradient
+            let (step_length, luma_local_average) = if gradient1.abs() >= gradient2.abs() {
+                (-1.0, 0.5 * (luma1 + luma_center))
+            } else {
+                (1.0, 0.5 * (luma2 + luma_center))
+            };
+
+            let position = Vec2::new(x as f32, y as f32);
+            let (edge_position, offset) = if is_horizontal {
+                (position + Vec2::new(0.0, step_length * 0.5), Vec2::X)
+            } else {
+                (position + Vec2::new(step_length * 0.5, 0.0), Vec2::Y)
+            };
+
+            // Walk along the edge in both directions until the luma changes enough
+            let mut position1 = edge_position - offset;
+            let mut position2 = edge_position + offset;
+            let mut luma_end1 = view.sample(position1) - luma_local_average;
+            let mut luma_end2 = view.sample(position2) - luma_local_average;
+            let mut reached1 = luma_end1.abs() >= gradient_scaled;
+            let mut reached2 = luma_end2.abs() >= gradient_scaled;
+
+            for step in SEARCH_STEPS.iter().skip(1) {
+                if reached1 && reached2 {
+                    break;
+                }
+                if !reached1 {
+                    position1 -= offset * *step;
+                    luma_end1 = view.sample(position1) - luma_local_average;
+                    reached1 = luma_end1.abs() >= gradient_scaled;
+                }
+                if !reached2 {
+                    position2 += offset * *step;
+                    luma_end2 = view.sample(position2) - luma_local_average;
+                    reached2 = luma_end2.abs() >= gradient_scaled;
+                }
+            }
+
+            let (distance1, distance2) = if is_horizontal {
+                (position.x - position1.x, position2.x - position.x)
+            } else {
+                (position.y - position1.y, position2.y - position.y)
+            };
+            let (distance, luma_end) = if distance1 < distance2 {
+                (distance1, luma_end1)
+            } else {
+                (distance2, luma_end2)
+            };
+            let edge_length = distance1 + distance2;
+
+            // Blend only if the end of the edge is on the expected side of the average
+            let is_luma_center_smaller = luma_center < luma_local_average;
+            let edge_offset = if (luma_end < 0.0) != is_luma_center_smaller {
+                0.5 - distance / edge_length
+            } else {
+                0.0
+            };
+
+            let luma_average = (1.0 / 12.0)
+                * (2.0 * (luma_down_up + luma_left_right) + luma_left_corners + luma_right_corners);
+            let subpixel_offset = ((luma_average - luma_center).abs() / luma_range).clamp(0.0, 1.0);
+            let subpixel_offset =
+                (-2.0 * subpixel_offset + 3.0) * subpixel_offset * subpixel_offset;
+            let subpixel_offset = subpixel_offset * subpixel_offset * SUBPIXEL_QUALITY;
+
+            let final_offset = edge_offset.max(subpixel_offset) * step_length;
+            let final_position = if is_horizontal {
+                position + Vec2::new(0.0, final_offset)
+            } else {
+                position + Vec2::new(final_offset, 0.0)
+            };
+
+            let idx = x as usize + y as usize * width;
+            output[idx] = vec_to_color(bilinear(final_position, get_color), source[idx].alpha);
+        }
+    }
+}
+
+fn luma(color: &Color) -> f32 {
+    (0.299 * color.r as f32 + 0.587 * color.g as f32 + 0.114 * color.b as f32) / 255.0
+}
+
+fn bilinear<T, F>(p: Vec2, get: F) -> T
+where
+    T: std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+    F: Fn(isize, isize) -> T,
+{
+    let base = p.floor();
+    let fract = p - base;
+    let (x, y) = (base.x as isize, base.y as isize);
+
+    let bottom = get(x, y) * (1.0 - fract.x) + get(x + 1, y) * fract.x;
+    let top = get(x, y + 1) * (1.0 - fract.x) + get(x + 1, y + 1) * fract.x;
+    bottom * (1.0 - fract.y) + top * fract.y
+}
+
+fn color_to_vec(color: Color) -> Vec3A {
+    Vec3A::new(color.r as f32, color.g as f32, color.b as f32)
+}
+
+fn vec_to_color(v: Vec3A, alpha: u8) -> Color {
+    let v = v.clamp(Vec3A::ZERO, Vec3A::splat(255.0));
+    Color::from_rgb_with_alpha(v.x as u8, v.y as u8, v.z as u8, alpha)
+}
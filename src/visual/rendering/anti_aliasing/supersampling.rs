@@ -0,0 +1,158 @@
+use glam::Vec4;
+
+use crate::{plane_buffer::plane_buffer::PlaneBuffer, visual::color::color::Color};
+
+// Number of lobes of the Lanczos kernel
+const LANCZOS_LOBES: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownsampleFilter {
+    Box,
+    Lanczos,
+}
+
+impl DownsampleFilter {
+    pub fn next(&self) -> DownsampleFilter {
+        match self {
+            DownsampleFilter::Box => DownsampleFilter::Lanczos,
+            DownsampleFilter::Lanczos => DownsampleFilter::Box,
+        }
+    }
+}
+
+/// Shrinks the supersampled image into the target.
+/// Source size must be the target size multiplied by an integer factor.
+pub fn downsample(
+    source: &PlaneBuffer<Color>,
+    target: &mut PlaneBuffer<Color>,
+    filter: DownsampleFilter,
+) -> Result<(), String> {
+    let (width, height) = (target.get_width(), target.get_height());
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    let factor = source.get_width() / width;
+    if factor == 0 || source.get_width() != width * factor || source.get_height() != height * factor
+    {
+        return Err(format!(
+            "Can't downsample {}x{} image into {}x{}",
+            source.get_width(),
+            source.get_height(),
+            width,
+            height
+        ));
+    }
+
+    match filter {
+        DownsampleFilter::Box => downsample_box(source, target, factor),
+        DownsampleFilter::Lanczos => downsample_lanczos(source, target, factor),
+    }
+    Ok(())
+}
+
+fn downsample_box(source: &PlaneBuffer<Color>, target: &mut PlaneBuffer<Color>, factor: usize) {
+    let source_width = source.get_width();
+    let target_width = target.get_width();
+    let samples_count = (factor * factor) as u32;
+    let source_buffer = source.get_buffer();
+
+    for (idx, color) in target.get_buffer_mut().iter_mut().enumerate() {
+        let (x, y) = (idx % target_width * factor, idx / target_width * factor);
+        let (mut b, mut g, mut r, mut alpha) = (0u32, 0u32, 0u32, 0u32);
+        for row in y..y + factor {
+            for sample in &source_buffer[x + row * source_width..x + factor + row * source_width] {
+                b += sample.b as u32;
+                g += sample.g as u32;
+                r += sample.r as u32;
+                alpha += sample.alpha as u32;
+            }
+        }
+        *color = Color::from_rgb_with_alpha(
+            (r / samples_count) as u8,
+            (g / samples_count) as u8,
+            (b / samples_count) as u8,
+            (alpha / samples_count) as u8,
+        );
+    }
+}
+
+// Separable filter: rows are shrunk first, then columns of the intermediate image
+fn downsample_lanczos(source: &PlaneBuffer<Color>, target: &mut PlaneBuffer<Color>, factor: usize) {
+    let (source_width, source_height) = (source.get_width(), source.get_height());
+    let target_width = target.get_width();
+    let taps = lanczos_taps(factor);
+
+    let source_buffer = source.get_buffer();
+    let mut rows = vec![Vec4::ZERO; target_width * source_height];
+    for y in 0..source_height {
+        for x in 0..target_width {
+            rows[x + y * target_width] = taps
+                .iter()
+                .map(|(offset, weight)| {
+                    let sx = (x as isize * factor as isize + offset)
+                        .clamp(0, source_width as isize - 1) as usize;
+                    color_to_vec(source_buffer[sx + y * source_width]) * *weight
+                })
+                .fold(Vec4::ZERO, |sum, v| sum + v);
+        }
+    }
+
+    for (idx, color) in target.get_buffer_mut().iter_mut().enumerate() {
+        let (x, y) = (idx % target_width, idx / target_width);
+        let v = taps
+            .iter()
+            .map(|(offset, weight)| {
+                let sy = (y as isize * factor as isize + offset)
+                    .clamp(0, source_height as isize - 1) as usize;
+                rows[x + sy * target_width] * *weight
+            })
+            .fold(Vec4::ZERO, |sum, v| sum + v);
+        *color = vec_to_color(v);
+    }
+}
+
+// Offsets of the source pixels from the first pixel of the block and their normalized weights
+fn lanczos_taps(factor: usize) -> Vec<(isize, f32)> {
+    let factor = factor as f32;
+    let center = (factor - 1.0) * 0.5;
+    let radius = LANCZOS_LOBES * factor;
+
+    let first = (center - radius).ceil() as isize;
+    let last = (center + radius).floor() as isize;
+    let mut taps: Vec<(isize, f32)> = (first..=last)
+        .map(|offset| (offset, lanczos((offset as f32 - center) / factor)))
+        .filter(|(_, weight)| *weight != 0.0)
+        .collect();
+
+    let total: f32 = taps.iter().map(|(_, weight)| weight).sum();
+    for (_, weight) in taps.iter_mut() {
+        *weight /= total;
+    }
+    taps
+}
+
+fn lanczos(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else if x.abs() < LANCZOS_LOBES {
+        let px = std::f32::consts::PI * x;
+        LANCZOS_LOBES * px.sin() * (px / LANCZOS_LOBES).sin() / (px * px)
+    } else {
+        0.0
+    }
+}
+
+fn color_to_vec(color: Color) -> Vec4 {
+    Vec4::new(
+        color.r as f32,
+        color.g as f32,
+        color.b as f32,
+        color.alpha as f32,
+    )
+}
+
+fn vec_to_color(v: Vec4) -> Color {
+    // Negative lobes may overshoot the valid range near sharp edges
+    let v = v.round().clamp(Vec4::ZERO, Vec4::splat(255.0));
+    Color::from_rgb_with_alpha(v.x as u8, v.y as u8, v.z as u8, v.w as u8)
+}
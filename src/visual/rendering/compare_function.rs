@@ -0,0 +1,44 @@
+use glam::{BVec4A, Vec4};
+
+/// Comparison of an incoming value against the stored one, used by the depth and stencil tests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+    Always,
+}
+
+impl CompareFunction {
+    /// Passes when `value <function> stored` holds
+    pub fn compare<T: PartialOrd>(&self, value: T, stored: T) -> bool {
+        match self {
+            CompareFunction::Never => false,
+            CompareFunction::Less => value < stored,
+            CompareFunction::LessEqual => value <= stored,
+            CompareFunction::Equal => value == stored,
+            CompareFunction::NotEqual => value != stored,
+            CompareFunction::GreaterEqual => value >= stored,
+            CompareFunction::Greater => value > stored,
+            CompareFunction::Always => true,
+        }
+    }
+
+    /// `compare` of the 4 lanes at once
+    pub fn compare_lanes(&self, values: Vec4, stored: Vec4) -> BVec4A {
+        match self {
+            CompareFunction::Never => BVec4A::new(false, false, false, false),
+            CompareFunction::Less => values.cmplt(stored),
+            CompareFunction::LessEqual => values.cmple(stored),
+            CompareFunction::Equal => values.cmpeq(stored),
+            CompareFunction::NotEqual => values.cmpne(stored),
+            CompareFunction::GreaterEqual => values.cmpge(stored),
+            CompareFunction::Greater => values.cmpgt(stored),
+            CompareFunction::Always => BVec4A::new(true, true, true, true),
+        }
+    }
+}
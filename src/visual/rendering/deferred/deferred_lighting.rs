@@ -0,0 +1,59 @@
+use glam::Vec4;
+use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::visual::{
+    color::color::Color, drawing_buffer::DrawingBuffer, rendering::light_source::LightSource,
+};
+
+use super::g_buffer::GBuffer;
+
+/// Screen space lighting pass: every pixel covered by the G-buffer is lit once,
+/// then its color and depth are written into all of the canvas samples.
+/// Pixels without a stored surface keep their content.
+/// The eye is the homogeneous viewer position in the lighting space.
+pub fn render_deferred_lighting(
+    g_buffer: &GBuffer,
+    canvas: &mut DrawingBuffer,
+    lights: &[LightSource],
+    eye: Vec4,
+) -> Result<(), String> {
+    let (width, height) = (g_buffer.get_width(), g_buffer.get_height());
+    if canvas.get_width() != width || canvas.get_height() != height {
+        return Err(format!(
+            "G-buffer size {}x{} doesn't match the canvas size {}x{}",
+            width,
+            height,
+            canvas.get_width(),
+            canvas.get_height()
+        ));
+    }
+
+    let depth_buffer = g_buffer.get_depth_buffer().get_buffer();
+    let lit: Vec<Option<Color>> = depth_buffer
+        .par_iter()
+        .enumerate()
+        .map(|(idx, _)| {
+            // Buffers are stored top row first, screen y grows upwards
+            let (x, y) = (idx % width, height - idx / width - 1);
+            g_buffer
+                .get_surface((x, y))
+                .map(|surface| surface.shade(eye, lights))
+        })
+        .collect();
+
+    for sample in 0..canvas.get_sample_count().get_count() {
+        let (color_buffer, z_buffer) = canvas.get_sample_buffers_mut(sample);
+        let pixels = color_buffer
+            .get_buffer_mut()
+            .iter_mut()
+            .zip(z_buffer.get_buffer_mut().iter_mut());
+        for ((color, z), (lit_color, &z_depth)) in pixels.zip(lit.iter().zip(depth_buffer.iter())) {
+            if let Some(lit_color) = lit_color {
+                *color = *lit_color;
+                *z = z_depth;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use glam::Vec3A;
+
+use crate::{
+    math::geometry::rect::Rect,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+    visual::{
+        color::color::Color,
+        rendering::{
+            shader::{
+                metallic_roughness::MetallicRoughness,
+                surface::{Specular, Surface},
+            },
+            tiling::tile_target::TileTarget,
+        },
+    },
+};
+
+/// Unlit surface attributes of the nearest fragments, one plane per attribute.
+/// Lighting is then evaluated once per covered pixel by the deferred lighting pass.
+pub struct GBuffer {
+    albedo: PlaneBuffer<Color>,
+    position: PlaneBuffer<Vec3A>,
+    normal: PlaneBuffer<Vec3A>,
+    specular: PlaneBuffer<Option<Specular>>,
+    emissive: PlaneBuffer<Vec3A>,
+    metallic_roughness: PlaneBuffer<Option<MetallicRoughness>>,
+    receives_shadows: PlaneBuffer<bool>,
+    depth: PlaneBuffer<f32>,
+    screen_rect: Rect,
+}
+
+impl GBuffer {
+    pub fn new(width: usize, height: usize) -> GBuffer {
+        GBuffer {
+            albedo: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            position: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            normal: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            specular: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            emissive: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            metallic_roughness: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            receives_shadows: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            depth: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Fill(|_| f32::MIN)),
+            screen_rect: Rect::new(0, 0, width, height),
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.depth.get_width()
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.depth.get_height()
+    }
+
+    pub fn get_albedo_buffer(&self) -> &PlaneBuffer<Color> {
+        &self.albedo
+    }
+
+    pub fn get_position_buffer(&self) -> &PlaneBuffer<Vec3A> {
+        &self.position
+    }
+
+    pub fn get_normal_buffer(&self) -> &PlaneBuffer<Vec3A> {
+        &self.normal
+    }
+
+    pub fn get_specular_buffer(&self) -> &PlaneBuffer<Option<Specular>> {
+        &self.specular
+    }
+
+    pub fn get_emissive_buffer(&self) -> &PlaneBuffer<Vec3A> {
+        &self.emissive
+    }
+
+    pub fn get_metallic_roughness_buffer(&self) -> &PlaneBuffer<Option<MetallicRoughness>> {
+        &self.metallic_roughness
+    }
+
+    pub fn get_receives_shadows_buffer(&self) -> &PlaneBuffer<bool> {
+        &self.receives_shadows
+    }
+
+    /// Larger depth is closer to the viewer, `f32::MIN` where nothing is drawn
+    pub fn get_depth_buffer(&self) -> &PlaneBuffer<f32> {
+        &self.depth
+    }
+
+    pub fn clean(&mut self) {
+        self.albedo.clean();
+        self.position.clean();
+        self.normal.clean();
+        self.specular.clean();
+        self.emissive.clean();
+        self.metallic_roughness.clean();
+        self.receives_shadows.clean();
+        self.depth.clean_with(&f32::MIN);
+    }
+
+    /// Surface stored at the pixel, `None` where nothing is drawn
+    pub fn get_surface(&self, p: (usize, usize)) -> Option<Surface> {
+        if self.depth[p] == f32::MIN {
+            return None;
+        }
+        Some(Surface {
+            albedo: self.albedo[p],
+            position: self.position[p],
+            normal: self.normal[p],
+            specular: self.specular[p],
+            emissive: self.emissive[p],
+            metallic_roughness: self.metallic_roughness[p],
+            receives_shadows: self.receives_shadows[p],
+        })
+    }
+
+    pub fn write(&mut self, p: (usize, usize), surface: &Surface, z_depth: f32) {
+        self.albedo[p] = surface.albedo;
+        self.position[p] = surface.position;
+        self.normal[p] = surface.normal;
+        self.specular[p] = surface.specular;
+        self.emissive[p] = surface.emissive;
+        self.metallic_roughness[p] = surface.metallic_roughness;
+        self.receives_shadows[p] = surface.receives_shadows;
+        self.depth[p] = z_depth;
+    }
+
+    fn to_local_rect(&self, rect: &Rect) -> Rect {
+        Rect {
+            x: rect.x - self.screen_rect.x,
+            y: rect.y - self.screen_rect.y,
+            size: rect.size,
+        }
+    }
+}
+
+impl TileTarget for GBuffer {
+    fn get_screen_rect(&self) -> Rect {
+        self.screen_rect
+    }
+
+    // Surfaces are stored per pixel
+    fn get_sample_radius(&self) -> f32 {
+        0.0
+    }
+
+    fn copy_tile(&self, tile: &Rect) -> GBuffer {
+        let local_tile = self.to_local_rect(tile);
+        GBuffer {
+            albedo: self.albedo.copy_region(&local_tile),
+            position: self.position.copy_region(&local_tile),
+            normal: self.normal.copy_region(&local_tile),
+            specular: self.specular.copy_region(&local_tile),
+            emissive: self.emissive.copy_region(&local_tile),
+            metallic_roughness: self.metallic_roughness.copy_region(&local_tile),
+            receives_shadows: self.receives_shadows.copy_region(&local_tile),
+            depth: self.depth.copy_region(&local_tile),
+            screen_rect: *tile,
+        }
+    }
+
+    fn paste_tile(&mut self, tile: &GBuffer) {
+        let local_tile = self.to_local_rect(&tile.screen_rect);
+        self.albedo.paste_region(&local_tile, &tile.albedo);
+        self.position.paste_region(&local_tile, &tile.position);
+        self.normal.paste_region(&local_tile, &tile.normal);
+        self.specular.paste_region(&local_tile, &tile.specular);
+        self.emissive.paste_region(&local_tile, &tile.emissive);
+        self.metallic_roughness
+            .paste_region(&local_tile, &tile.metallic_roughness);
+        self.receives_shadows
+            .paste_region(&local_tile, &tile.receives_shadows);
+        self.depth.paste_region(&local_tile, &tile.depth);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::visual::{
+    rendering::{
+        render_state::RenderState,
+        shader::{
+            shader::{Fragment, ShadedFace, Varyings},
+            surface::SurfaceShader,
+        },
+        tiling::tile_target::TileTarget,
+        triangle::triangle_scanline::scan_triangle,
+    },
+    vertex::ScreenTriangle,
+};
+
+use super::g_buffer::GBuffer;
+
+/// Scanline rasterization of the face into the G-buffer.
+/// Only the depth test and the alpha test of the state apply,
+/// the stored depth always follows the stored surface.
+pub fn render_triangle_g_buffer<S: SurfaceShader>(
+    face: &ShadedFace<S::Varyings>,
+    g_buffer: &mut GBuffer,
+    shader: &S,
+    state: &RenderState,
+) {
+    let varyings = face.vertices.each_ref().map(|vertex| &vertex.varyings);
+    let screen_rect = g_buffer.get_screen_rect();
+
+    scan_triangle(
+        face.get_screen_positions(),
+        screen_rect,
+        |p, screen_pos, barycentric| {
+            if !state
+                .depth_compare
+                .compare(screen_pos.z, g_buffer.get_depth_buffer()[p])
+            {
+                return;
+            }
+
+            let fragment = Fragment {
+                screen_pos,
+                varyings: S::Varyings::interpolate(varyings, barycentric),
+                face,
+            };
+            let surface = shader
+                .surface(&fragment)
+                .filter(|surface| state.passes_alpha_test(surface.albedo));
+            if let Some(surface) = surface {
+                g_buffer.write(p, &surface, screen_pos.z);
+            }
+        },
+    );
+}
@@ -0,0 +1,69 @@
+use glam::Vec3A;
+
+use crate::visual::rendering::compare_function::CompareFunction;
+
+/// Depth offset of the faces, as `glPolygonOffset` does.
+/// Positive values move the faces away from the viewer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PolygonOffset {
+    /// Scales the maximum depth slope of the face
+    pub factor: f32,
+    /// Scales the smallest resolvable depth difference at the face
+    pub units: f32,
+}
+
+impl PolygonOffset {
+    pub fn is_none(&self) -> bool {
+        self.factor == 0.0 && self.units == 0.0
+    }
+
+    /// Offset to subtract from the face depth, larger depth is closer to the viewer
+    pub fn calculate(&self, [p0, p1, p2]: [Vec3A; 3]) -> f32 {
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let det = e1.x * e2.y - e2.x * e1.y;
+        let max_slope = if det == 0.0 {
+            0.0
+        } else {
+            let dz_dx = (e1.z * e2.y - e2.z * e1.y) / det;
+            let dz_dy = (e2.z * e1.x - e1.z * e2.x) / det;
+            dz_dx.abs().max(dz_dy.abs())
+        };
+
+        // Depth is stored as f32, so the resolution depends on the magnitude
+        let max_depth = p0.z.abs().max(p1.z.abs()).max(p2.z.abs());
+        self.factor * max_slope + self.units * max_depth * f32::EPSILON
+    }
+}
+
+/// Depth test and write settings of the model draws
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthState {
+    /// Compares the fragment depth with the stored one
+    pub compare: CompareFunction,
+    /// Transparent models never write depth
+    pub write: bool,
+    pub offset: PolygonOffset,
+}
+
+impl DepthState {
+    /// Depth the buffer is cleared to so that every fragment passes against it,
+    /// the farthest one in the direction of the compare function.
+    /// Models sharing a buffer can only mix the compare functions of the same direction,
+    /// `Less` ones never pass against the clear depth of `Greater` ones and vice versa.
+    pub fn get_clear_depth(&self) -> f32 {
+        match self.compare {
+            CompareFunction::Less | CompareFunction::LessEqual => f32::MAX,
+            _ => f32::MIN,
+        }
+    }
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        DepthState {
+            compare: CompareFunction::GreaterEqual,
+            write: true,
+            offset: PolygonOffset::default(),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::visual::vertex::ScreenTriangle;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrontFace {
+    Cw,
+    Ccw,
+}
+
+impl FrontFace {
+    pub fn is_front_facing<T: ScreenTriangle>(&self, triangle: &T) -> bool {
+        // Screen space has y pointing up (see `PlaneBuffer` indexing),
+        // so a positive signed area means counter-clockwise winding
+        let [a, b, c] = triangle.get_screen_positions();
+        let signed_area = (b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y);
+        match self {
+            FrontFace::Ccw => signed_area > 0.0,
+            FrontFace::Cw => signed_area < 0.0,
+        }
+    }
+}
+
+impl CullMode {
+    pub fn culls(&self, is_front_facing: bool) -> bool {
+        match self {
+            CullMode::None => false,
+            CullMode::Back => !is_front_facing,
+            CullMode::Front => is_front_facing,
+        }
+    }
+}
@@ -1,13 +1,90 @@
-use glam::{Vec3A, Mat4};
+use std::sync::Arc;
 
-use crate::plane_buffer::plane_buffer::PlaneBuffer;
+use glam::Vec3A;
 
+use super::shadow::{
+    cube_shadow_map::CubeShadowMap,
+    shadow_map::ShadowMap,
+    shadow_settings::{CubeShadowSettings, ShadowSettings},
+};
+
+/// Falloff of the point light intensity with the distance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attenuation {
+    /// `1 / (constant + linear * d + quadratic * d^2)`
+    Polynomial {
+        constant: f32,
+        linear: f32,
+        quadratic: f32,
+    },
+    /// Physically based `1 / (d^2 + 1)`, windowed to reach zero at the radius
+    InverseSquare { radius: f32 },
+}
+
+impl Attenuation {
+    pub fn calculate(&self, distance: f32) -> f32 {
+        match *self {
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => {
+                let falloff = constant + linear * distance + quadratic * distance * distance;
+                1.0 / falloff.max(f32::EPSILON)
+            }
+            Attenuation::InverseSquare { radius } => {
+                let window = (1.0 - (distance / radius).powi(4)).clamp(0.0, 1.0);
+                window * window / (distance * distance + 1.0)
+            }
+        }
+    }
+}
+
+/// Cone of the spot light, angles are in radians from the spot direction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpotCone {
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+}
+
+impl SpotCone {
+    /// Full intensity within the inner angle, smoothly fading out to zero at the outer angle
+    pub fn calculate(&self, angle_cos: f32) -> f32 {
+        let (inner_cos, outer_cos) = (self.inner_angle.cos(), self.outer_angle.cos());
+        if inner_cos <= outer_cos {
+            return if angle_cos >= outer_cos { 1.0 } else { 0.0 };
+        }
+        let t = ((angle_cos - outer_cos) / (inner_cos - outer_cos)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Shadow maps are rendered by the shadow pass, lights without them cast no shadows
 #[derive(Clone, Debug)]
 pub enum LightSourceKind {
+    /// Directional light, its shadow map cascades go from the nearest to the viewer
     Linear {
         dir: Vec3A,
-        shadow_buffer: Option<PlaneBuffer<f32>>,
-        transform_matrix: Option<Mat4>
+        shadow: ShadowSettings,
+        shadow_maps: Vec<ShadowMap>,
+    },
+    /// Light emitted in all directions from the position,
+    /// which is in the same space as the directions of the linear lights.
+    /// Its cube shadow map is shared by the clones of the light and reused while it's valid.
+    Point {
+        position: Vec3A,
+        attenuation: Attenuation,
+        shadow: CubeShadowSettings,
+        shadow_map: Option<Arc<CubeShadowMap>>,
+    },
+    /// Point light limited to the cone around the direction it shines in
+    Spot {
+        position: Vec3A,
+        dir: Vec3A,
+        cone: SpotCone,
+        attenuation: Attenuation,
+        shadow: ShadowSettings,
+        shadow_map: Option<ShadowMap>,
     },
     Ambient,
 }
@@ -0,0 +1,51 @@
+use glam::Vec2;
+
+use crate::math::geometry::{primitives::line::Line, rect::Rect};
+
+/// Liang–Barsky clipping of the line against the `min..max` box.
+/// Returns range of the line parameter `t` (0 — begin, 1 — end) inside the box.
+pub fn clip_line_range(line: &Line, min: Vec2, max: Vec2) -> Option<(f32, f32)> {
+    let delta = line.end - line.begin;
+    let (mut t_begin, mut t_end) = (0.0f32, 1.0f32);
+
+    let boundaries = [
+        (-delta.x, line.begin.x - min.x),
+        (delta.x, max.x - line.begin.x),
+        (-delta.y, line.begin.y - min.y),
+        (delta.y, max.y - line.begin.y),
+    ];
+    for (p, q) in boundaries {
+        if p == 0.0 {
+            // Parallel to the boundary and outside of it
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+
+        let t = q / p;
+        if p < 0.0 {
+            t_begin = t_begin.max(t);
+        } else {
+            t_end = t_end.min(t);
+        }
+        if t_begin > t_end {
+            return None;
+        }
+    }
+
+    Some((t_begin, t_end))
+}
+
+/// Part of the line inside the rect, depth is interpolated along with position
+pub fn clip_line(line: &Line, bounds: &Rect) -> Option<Line> {
+    let min = Vec2::new(bounds.x as f32, bounds.y as f32);
+    let max = min + Vec2::new(bounds.size.width as f32, bounds.size.height as f32);
+    let (t_begin, t_end) = clip_line_range(line, min, max)?;
+
+    let delta = line.end - line.begin;
+    Some(Line::new(
+        line.begin + delta * t_begin,
+        line.begin + delta * t_end,
+    ))
+}
@@ -1,11 +1,149 @@
+use glam::Vec2;
+
 use crate::{
     math::geometry::primitives::line::Line,
     visual::{color::color::Color, drawing_buffer::DrawingBuffer},
 };
 
-pub fn draw_line(line: &Line, canvas: &mut DrawingBuffer, color: Option<&Color>) {
-    let line_iterator = line.into_iter();
-    for point in line_iterator {
-        canvas[(point.x as usize, point.y as usize)] = unsafe { *color.unwrap_unchecked() };
+use super::{
+    line_clipping::{clip_line, clip_line_range},
+    line_style::LineStyle,
+};
+
+pub fn draw_line(line: &Line, canvas: &mut DrawingBuffer, color: &Color) {
+    let rect = canvas.get_screen_rect();
+    let line = match clip_line(line, &rect) {
+        Some(line) => line,
+        None => return,
+    };
+
+    for point in line.into_iter() {
+        let (x, y) = (point.x - rect.x as i32, point.y - rect.y as i32);
+        if x < 0 || y < 0 || !canvas.contains(x as usize, y as usize) {
+            continue;
+        }
+        canvas[(x as usize, y as usize)] = *color;
     }
 }
+
+/// Draws the line with thickness, dashes, color gradient and optional anti-aliasing
+/// (Xiaolin Wu style coverage, extended to thick lines).
+/// With depth offset the line is drawn only where it is not hidden behind the z-buffer contents,
+/// offset moves it towards the viewer so it isn't hidden by the faces it lies on.
+pub fn draw_line_styled(
+    line: &Line,
+    canvas: &mut DrawingBuffer,
+    style: &LineStyle,
+    depth_offset: Option<f32>,
+) {
+    let rect = canvas.get_screen_rect();
+    let delta = line.end - line.begin;
+    let length = Vec2::new(delta.x, delta.y).length();
+    if length == 0.0 {
+        return;
+    }
+
+    // Thick lines may cover the canvas while their center line is outside of it
+    let margin = Vec2::splat(style.thickness + 1.0);
+    let (t_begin, t_end) = match clip_line_range(
+        line,
+        Vec2::new(rect.x as f32, rect.y as f32) - margin,
+        Vec2::new(
+            (rect.x + rect.size.width) as f32,
+            (rect.y + rect.size.height) as f32,
+        ) + margin,
+    ) {
+        Some(range) => range,
+        None => return,
+    };
+
+    // Line is walked along the major axis, spans of pixels are filled along the minor one
+    let steep = delta.y.abs() > delta.x.abs();
+    let (major, minor) = if steep { (1, 0) } else { (0, 1) };
+
+    let gradient = delta[minor] / delta[major];
+    let half_width = if style.anti_aliased {
+        style.thickness * 0.5
+    } else {
+        style.thickness.max(1.0) * 0.5
+    } * (1.0 + gradient * gradient).sqrt();
+
+    let major_ends = (
+        (line.begin + delta * t_begin)[major],
+        (line.begin + delta * t_end)[major],
+    );
+    let (major_from, major_to) = (
+        major_ends.0.min(major_ends.1),
+        major_ends.0.max(major_ends.1),
+    );
+
+    for major_pos in major_from.floor() as i32..major_to.ceil() as i32 {
+        let major_center = major_pos as f32 + 0.5;
+        let major_coverage = if style.anti_aliased {
+            coverage(major_pos, major_from, major_to)
+        } else if (major_from..major_to).contains(&major_center) {
+            1.0
+        } else {
+            0.0
+        };
+        if major_coverage <= 0.0 {
+            continue;
+        }
+
+        let t = (major_center.clamp(major_from, major_to) - line.begin[major]) / delta[major];
+        if let Some(dash_pattern) = style.dash_pattern {
+            let position = (t * length).rem_euclid(dash_pattern.dash + dash_pattern.gap);
+            if position >= dash_pattern.dash {
+                continue;
+            }
+        }
+
+        let color = style.begin_color.lerp(style.end_color, t);
+        let z = line.begin.z + delta.z * t;
+        let minor_center = line.begin[minor] + delta[minor] * t;
+        let (minor_from, minor_to) = (minor_center - half_width, minor_center + half_width);
+
+        for minor_pos in minor_from.floor() as i32..minor_to.ceil() as i32 {
+            let minor_coverage = if style.anti_aliased {
+                coverage(minor_pos, minor_from, minor_to)
+            } else if (minor_from..minor_to).contains(&(minor_pos as f32 + 0.5)) {
+                1.0
+            } else {
+                0.0
+            };
+            let alpha = major_coverage * minor_coverage;
+            if alpha <= 0.0 {
+                continue;
+            }
+
+            let (x, y) = if steep {
+                (minor_pos, major_pos)
+            } else {
+                (major_pos, minor_pos)
+            };
+            let (x, y) = (x - rect.x as i32, y - rect.y as i32);
+            if x < 0 || y < 0 || !canvas.contains(x as usize, y as usize) {
+                continue;
+            }
+            let p = (x as usize, y as usize);
+
+            if let Some(depth_offset) = depth_offset {
+                if z + depth_offset < canvas.get_z_buffer()[p] {
+                    continue;
+                }
+            }
+
+            canvas[p] = if alpha < 1.0 {
+                canvas[p].lerp(color, alpha)
+            } else {
+                color
+            };
+        }
+    }
+}
+
+// Length of the pixel's `[pos, pos + 1)` part which is covered by the `from..to` range
+fn coverage(pos: i32, from: f32, to: f32) -> f32 {
+    let pos = pos as f32;
+    (to.min(pos + 1.0) - from.max(pos)).clamp(0.0, 1.0)
+}
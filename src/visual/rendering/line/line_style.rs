@@ -0,0 +1,30 @@
+use crate::visual::color::color::Color;
+
+/// Lengths of the drawn and skipped parts of a dashed line, in pixels
+#[derive(Clone, Copy, Debug)]
+pub struct DashPattern {
+    pub dash: f32,
+    pub gap: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LineStyle {
+    pub begin_color: Color,
+    pub end_color: Color,
+    pub thickness: f32,
+    pub anti_aliased: bool,
+    pub dash_pattern: Option<DashPattern>,
+}
+
+impl LineStyle {
+    /// Solid aliased 1px line of a single color
+    pub fn new(color: Color) -> LineStyle {
+        LineStyle {
+            begin_color: color,
+            end_color: color,
+            thickness: 1.0,
+            anti_aliased: false,
+            dash_pattern: None,
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use glam::{Vec3A, Vec4};
+
+use super::{texture::sampler::Sampler, transparency::blend_mode::BlendMode};
+
+/// Lighting model of the material
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShadingModel {
+    /// Lambertian diffuse with the Phong or Blinn-Phong highlight
+    DiffuseSpecular,
+    /// Physically based Cook-Torrance BRDF of the metallic-roughness workflow
+    MetallicRoughness,
+}
+
+impl ShadingModel {
+    pub fn next(&self) -> ShadingModel {
+        match self {
+            ShadingModel::DiffuseSpecular => ShadingModel::MetallicRoughness,
+            ShadingModel::MetallicRoughness => ShadingModel::DiffuseSpecular,
+        }
+    }
+}
+
+/// Factors of the metallic-roughness shading, multiplied with the maps of the model where present
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PbrMaterial {
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Blend from no occlusion to the full occlusion of the map
+    pub occlusion_strength: f32,
+    /// Linear intensities added to the glow map emission
+    pub emissive: Vec3A,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        PbrMaterial {
+            metallic: 0.0,
+            roughness: 0.5,
+            occlusion_strength: 1.0,
+            emissive: Vec3A::ZERO,
+        }
+    }
+}
+
+/// Specular reflection model
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecularModel {
+    /// Reflected light direction compared with the view direction
+    Phong,
+    /// Normal compared with the half vector between the light and view directions
+    BlinnPhong,
+}
+
+impl SpecularModel {
+    pub fn next(&self) -> SpecularModel {
+        match self {
+            SpecularModel::Phong => SpecularModel::BlinnPhong,
+            SpecularModel::BlinnPhong => SpecularModel::Phong,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChannel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl ColorChannel {
+    pub fn get(&self, rgba: Vec4) -> f32 {
+        match self {
+            ColorChannel::Red => rgba.x,
+            ColorChannel::Green => rgba.y,
+            ColorChannel::Blue => rgba.z,
+            ColorChannel::Alpha => rgba.w,
+        }
+    }
+}
+
+/// Specular highlight of the material
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpecularMaterial {
+    pub model: SpecularModel,
+    /// Reflectance of the highlight, it isn't tinted by the diffuse texture
+    pub color: Vec3A,
+    /// Exponent used where there is no specular map, no highlight without both
+    pub shininess: Option<f32>,
+    /// Specular map channel read into the exponent
+    pub map_channel: ColorChannel,
+    /// Exponent is `(1 - value) * map_scale` when inverted, `value * map_scale` otherwise
+    pub map_invert: bool,
+    pub map_scale: f32,
+    /// Normalizes the highlight by its exponent and dims the diffuse light by the specular color,
+    /// so the surface doesn't reflect more light than it receives
+    pub energy_conservation: bool,
+}
+
+impl SpecularMaterial {
+    /// Exponent for the sampled specular map texel
+    pub fn map_shininess(&self, texel: Vec4) -> f32 {
+        let value = self.map_channel.get(texel);
+        let value = if self.map_invert { 1.0 - value } else { value };
+        value * self.map_scale
+    }
+}
+
+impl Default for SpecularMaterial {
+    fn default() -> Self {
+        SpecularMaterial {
+            model: SpecularModel::BlinnPhong,
+            color: Vec3A::ONE * 0.3,
+            shininess: None,
+            map_channel: ColorChannel::Blue,
+            map_invert: true,
+            // Blinn-Phong needs about 4 times the Phong exponent for the same highlight size
+            map_scale: 255.0 / 8.0,
+            energy_conservation: false,
+        }
+    }
+}
+
+/// Surface properties of the model, which are not stored in its textures
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// Multiplied with the texture alpha, used only by the blended modes
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+    /// Fragments with alpha below the cutoff are discarded, e.g. for foliage or fences
+    pub alpha_cutoff: Option<f32>,
+    pub shading_model: ShadingModel,
+    /// Used by the diffuse and specular shading
+    pub specular: SpecularMaterial,
+    /// Used by the metallic-roughness shading
+    pub pbr: PbrMaterial,
+    /// Sampler shared by the diffuse texture and the normal, specular and glow maps
+    pub sampler: Sampler,
+}
+
+impl Material {
+    pub fn is_transparent(&self) -> bool {
+        self.blend_mode != BlendMode::Opaque
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            opacity: 1.0,
+            blend_mode: BlendMode::Opaque,
+            alpha_cutoff: None,
+            shading_model: ShadingModel::DiffuseSpecular,
+            specular: SpecularMaterial::default(),
+            pbr: PbrMaterial::default(),
+            sampler: Sampler::default(),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use glam::Mat4;
+
+/// Matrixes placing the models of the scene on the screen
+#[derive(Clone, Copy, Debug)]
+pub struct CameraMatrixes {
+    pub viewport_matrix: Mat4,
+    pub projection: Mat4,
+    pub view_matrix: Mat4,
+    /// Rotation of the scene by the mouse, applied before the view matrix
+    pub rotation_matrix: Mat4,
+}
+
+impl CameraMatrixes {
+    /// Model space to screen space
+    pub fn get_transform_matrix(&self, model_matrix: Mat4) -> Mat4 {
+        self.viewport_matrix
+            * self.projection
+            * model_matrix
+            * self.rotation_matrix
+            * self.view_matrix
+    }
+
+    /// Transforms the model normals into the (rotated) lighting space
+    pub fn get_normal_matrix(&self) -> Mat4 {
+        self.rotation_matrix.transpose().inverse()
+    }
+}
@@ -1,7 +1,50 @@
-use glam::Mat4;
+use glam::{Mat4, Vec3, Vec3A, Vec4};
 
 pub fn create_projection_matrix(distance: f32) -> Mat4 {
     let mut m = Mat4::IDENTITY;
     m.col_mut(2)[3] = -1.0 / distance;
     m
 }
+
+/// Homogeneous position of the viewer in the space transformed by the projection,
+/// `w` is zero for the orthographic projections whose viewer is infinitely far
+pub fn get_projection_eye(projection: Mat4) -> Vec4 {
+    // Eye is the point projected to infinity straight ahead
+    projection.inverse() * Vec4::Z
+}
+
+/// Orthographic projection of the box onto the -1..1 cube,
+/// the larger z stays closer to the viewer
+pub fn create_orthographic_projection_matrix(min: Vec3A, max: Vec3A) -> Mat4 {
+    let size = (max - min).max(Vec3A::splat(f32::EPSILON));
+    Mat4::from_translation((-(max + min) / size).into()) * Mat4::from_scale((2.0 / size).into())
+}
+
+/// View and projection of the spot light, the outer cone angle
+/// spans the -1..1 range of the screen x and y
+pub fn create_spot_projection_matrix(position: Vec3A, dir: Vec3A, outer_angle: f32) -> Mat4 {
+    let distance = 1.0 / outer_angle.tan();
+    let dir = dir.normalize();
+    let up = if dir.abs_diff_eq(Vec3A::Y, 1e-3) || dir.abs_diff_eq(Vec3A::NEG_Y, 1e-3) {
+        Vec3A::Z
+    } else {
+        Vec3A::Y
+    };
+    // Looks down -Z from the spot, then moves it to `distance` where the projection has its eye
+    let view_matrix = Mat4::from_translation(Vec3::Z * distance)
+        * Mat4::look_at_rh(position.into(), (position + dir).into(), up.into());
+    create_projection_matrix(distance) * view_matrix
+}
+
+/// Perspective projection with near and far planes, given as distances from the camera.
+/// Depth is reversed-Z: the near plane maps to 1 and the far plane to -1, so that
+/// with the viewport transform the visible range fills the whole z-buffer range,
+/// while the larger depth is still closer to the viewer.
+/// Screen x and y are the same as with `create_projection_matrix`.
+pub fn create_reversed_z_projection_matrix(distance: f32, near: f32, far: f32) -> Mat4 {
+    let mut m = create_projection_matrix(distance);
+    let depth_scale = distance * (far - near);
+    m.col_mut(2)[2] = (far + near) / depth_scale;
+    m.col_mut(3)[2] = (2.0 * near * far - distance * (far + near)) / depth_scale;
+    m
+}
@@ -1,8 +1,15 @@
 pub mod line {
+    pub mod line_clipping;
     pub mod line_iterators;
     pub mod line_rasterization;
+    pub mod line_style;
+}
+pub mod point {
+    pub mod point_rasterization;
+    pub mod point_style;
 }
 pub mod wavefront_obj {
+    pub mod wavefront_obj_points;
     pub mod wavefront_obj_rendering;
     pub mod wavefront_render_model;
     pub mod wavefront_obj_depth;
@@ -10,13 +17,74 @@ pub mod wavefront_obj {
 }
 pub mod triangle {
     pub mod triangle_rasterization;
+    pub mod triangle_rasterization_simd;
     pub mod triangle_depth;
+    pub mod triangle_scanline;
     pub mod interpolation_values;
 }
+pub mod shadow {
+    pub mod cube_shadow_map;
+    pub mod shadow_map;
+    pub mod shadow_pass;
+    pub mod shadow_settings;
+}
+pub mod shader {
+    pub mod default_shader;
+    pub mod metallic_roughness;
+    pub mod multi_target_shader;
+    pub mod normal_shader;
+    pub mod outline_shader;
+    #[allow(clippy::module_inception)]
+    pub mod shader;
+    pub mod surface;
+}
+pub mod texture {
+    pub mod sampler;
+    #[allow(clippy::module_inception)]
+    pub mod texture;
+}
+pub mod deferred {
+    pub mod deferred_lighting;
+    pub mod g_buffer;
+    pub mod g_buffer_rasterization;
+}
+pub mod depth {
+    pub mod depth_state;
+}
+pub mod occlusion {
+    pub mod hi_z_buffer;
+}
+pub mod render_target {
+    pub mod framebuffer;
+    pub mod framebuffer_rasterization;
+    #[allow(clippy::module_inception)]
+    pub mod render_target;
+}
+pub mod stencil {
+    pub mod stencil_state;
+}
+pub mod transparency {
+    pub mod blend_mode;
+    pub mod weighted_oit;
+}
+pub mod tiling {
+    pub mod tile_grid;
+    pub mod tiled_rasterizer;
+    pub mod tile_target;
+}
+pub mod anti_aliasing {
+    pub mod fxaa;
+    pub mod supersampling;
+}
 pub mod matrix {
+    pub mod camera_matrixes;
     pub mod view_matrix;
     pub mod viewport_matrix;
     pub mod projection_matrix;
 }
 pub mod ambient_occlusion;
-pub mod light_source;
\ No newline at end of file
+pub mod compare_function;
+pub mod face_culling;
+pub mod light_source;
+pub mod material;
+pub mod render_state;
\ No newline at end of file
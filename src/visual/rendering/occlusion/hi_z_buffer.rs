@@ -0,0 +1,165 @@
+use glam::Vec3A;
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+
+use crate::{
+    math::geometry::rect::Rect,
+    visual::{drawing_buffer::DrawingBuffer, rendering::compare_function::CompareFunction},
+};
+
+// Side of the finest level cells in pixels
+const BASE_CELL_SIZE: usize = 8;
+
+struct HiZLevel {
+    columns: usize,
+    rows: usize,
+    // Farthest and nearest stored depth of the cells, larger depth is closer
+    min: Vec<f32>,
+    max: Vec<f32>,
+}
+
+impl HiZLevel {
+    fn new(columns: usize, rows: usize) -> HiZLevel {
+        HiZLevel {
+            columns,
+            rows,
+            min: vec![f32::MAX; columns * rows],
+            max: vec![f32::MIN; columns * rows],
+        }
+    }
+
+    // Each cell covers the 2x2 block of the previous level cells
+    fn downsample(&self) -> HiZLevel {
+        let mut level = HiZLevel::new(self.columns.div_ceil(2), self.rows.div_ceil(2));
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let (idx, cell_idx) = (
+                    column + row * self.columns,
+                    column / 2 + row / 2 * level.columns,
+                );
+                level.min[cell_idx] = level.min[cell_idx].min(self.min[idx]);
+                level.max[cell_idx] = level.max[cell_idx].max(self.max[idx]);
+            }
+        }
+        level
+    }
+}
+
+/// Hierarchical z-buffer: pyramid of min/max depth over the cells of the canvas,
+/// each level halving the resolution of the previous one down to a single cell.
+/// Tells whether an area would fail the depth test everywhere,
+/// so that hidden models and faces can be skipped before shading.
+pub struct HiZBuffer {
+    screen_rect: Rect,
+    // Distance of the samples from the pixel they belong to
+    sample_radius: f32,
+    levels: Vec<HiZLevel>,
+}
+
+impl HiZBuffer {
+    /// Built from the depth of every sample of the canvas
+    pub fn new(canvas: &DrawingBuffer) -> HiZBuffer {
+        let (width, height) = (canvas.get_width(), canvas.get_height());
+        let mut base = HiZLevel::new(
+            width.div_ceil(BASE_CELL_SIZE),
+            height.div_ceil(BASE_CELL_SIZE),
+        );
+
+        let sample_count = canvas.get_sample_count();
+        let z_buffers: Vec<&[f32]> = (0..sample_count.get_count())
+            .map(|sample| canvas.get_sample_buffers(sample).1.get_buffer().as_slice())
+            .collect();
+        let columns = base.columns;
+        base.min
+            .par_chunks_mut(columns)
+            .zip(base.max.par_chunks_mut(columns))
+            .enumerate()
+            .for_each(|(cell_row, (min, max))| {
+                let rows = cell_row * BASE_CELL_SIZE..((cell_row + 1) * BASE_CELL_SIZE).min(height);
+                for z_buffer in z_buffers.iter() {
+                    for y in rows.clone() {
+                        let row = &z_buffer[(height - y - 1) * width..(height - y) * width];
+                        let cells = row.chunks(BASE_CELL_SIZE);
+                        for ((cell, min), max) in cells.zip(min.iter_mut()).zip(max.iter_mut()) {
+                            *min = cell.iter().fold(*min, |acc, z_depth| acc.min(*z_depth));
+                            *max = cell.iter().fold(*max, |acc, z_depth| acc.max(*z_depth));
+                        }
+                    }
+                }
+            });
+
+        let mut levels = vec![base];
+        loop {
+            let last = levels.last().unwrap();
+            if last.columns == 1 && last.rows == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+
+        HiZBuffer {
+            screen_rect: canvas.get_screen_rect(),
+            sample_radius: sample_count.get_max_offset(),
+            levels,
+        }
+    }
+
+    /// Whether every fragment of the screen space box `min..=max` would fail the depth test.
+    /// Areas outside of the canvas are left to the rasterizer.
+    pub fn is_occluded(&self, min: Vec3A, max: Vec3A, compare: CompareFunction) -> bool {
+        let x_range = self.screen_rect.get_x_range();
+        let y_range = self.screen_rect.get_y_range();
+        let (x0, y0) = (
+            (min.x - self.sample_radius).floor(),
+            (min.y - self.sample_radius).floor(),
+        );
+        let (x1, y1) = (
+            (max.x + self.sample_radius).floor(),
+            (max.y + self.sample_radius).floor(),
+        );
+        if x0 < x_range.start as f32
+            || y0 < y_range.start as f32
+            || x1 >= x_range.end as f32
+            || y1 >= y_range.end as f32
+        {
+            return false;
+        }
+
+        let (x0, y0) = (x0 as usize - x_range.start, y0 as usize - y_range.start);
+        let (x1, y1) = (x1 as usize - x_range.start, y1 as usize - y_range.start);
+
+        // The finest level, where the box spans at most 2x2 cells
+        let mut cell_size = BASE_CELL_SIZE;
+        let mut level_idx = 0;
+        while level_idx + 1 < self.levels.len()
+            && (x1 / cell_size - x0 / cell_size > 1 || y1 / cell_size - y0 / cell_size > 1)
+        {
+            cell_size *= 2;
+            level_idx += 1;
+        }
+
+        let level = &self.levels[level_idx];
+        for row in y0 / cell_size..=y1 / cell_size {
+            for column in x0 / cell_size..=x1 / cell_size {
+                let idx = column + row * level.columns;
+                let (cell_min, cell_max) = (level.min[idx], level.max[idx]);
+                let occluded = match compare {
+                    CompareFunction::Never => true,
+                    CompareFunction::Less => min.z >= cell_max,
+                    CompareFunction::LessEqual => min.z > cell_max,
+                    CompareFunction::Equal => max.z < cell_min || min.z > cell_max,
+                    CompareFunction::GreaterEqual => max.z < cell_min,
+                    CompareFunction::Greater => max.z <= cell_min,
+                    CompareFunction::NotEqual | CompareFunction::Always => false,
+                };
+                if !occluded {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,59 @@
+use glam::{Vec2, Vec3A};
+
+use crate::visual::{
+    color::color::Color, drawing_buffer::DrawingBuffer, rendering::depth::depth_state::DepthState,
+};
+
+use super::point_style::PointShape;
+
+/// Depth tested splat centered at the screen position.
+/// Coverage is tested per sample, so points are anti-aliased with multisampling.
+pub fn draw_point(
+    position: Vec3A,
+    canvas: &mut DrawingBuffer,
+    size: f32,
+    shape: PointShape,
+    color: &Color,
+    depth: &DepthState,
+) {
+    let rect = canvas.get_screen_rect();
+    let sample_count = canvas.get_sample_count();
+    let radius = size.max(1.0) * 0.5;
+
+    let center = Vec2::new(position.x, position.y);
+    // Pixel centers are at half-integer coordinates, as in the triangle rasterizers
+    let extent = radius + sample_count.get_max_offset();
+    let min = (center - extent - 0.5)
+        .ceil()
+        .max(Vec2::new(rect.x as f32, rect.y as f32));
+    let max = ((center + extent - 0.5).floor() + Vec2::ONE).min(Vec2::new(
+        (rect.x + rect.size.width) as f32,
+        (rect.y + rect.size.height) as f32,
+    ));
+
+    for y in min.y as usize..max.y.max(min.y) as usize {
+        for x in min.x as usize..max.x.max(min.x) as usize {
+            let p = (x - rect.x, y - rect.y);
+            let pixel_center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+            for (sample, offset) in sample_count.get_positions().iter().enumerate() {
+                let d = pixel_center + *offset - center;
+                let covered = match shape {
+                    PointShape::Square => d.abs().max_element() <= radius,
+                    PointShape::Round => d.length_squared() <= radius * radius,
+                };
+                if !covered {
+                    continue;
+                }
+
+                let (color_buffer, z_buffer) = canvas.get_sample_buffers_mut(sample);
+                if depth.compare.compare(position.z, z_buffer[p]) {
+                    if depth.write {
+                        z_buffer[p] = position.z;
+                    }
+                    color_buffer[p] = *color;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointShape {
+    Square,
+    Round,
+}
+
+impl PointShape {
+    pub fn next(&self) -> PointShape {
+        match self {
+            PointShape::Square => PointShape::Round,
+            PointShape::Round => PointShape::Square,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointColoring {
+    /// Colors from `v x y z r g b` lines, white if the model has none
+    Vertex,
+    /// Normal direction mapped into RGB, vertex colors are used
+    /// if the normals don't match the vertices one to one
+    Normal,
+}
+
+impl PointColoring {
+    pub fn next(&self) -> PointColoring {
+        match self {
+            PointColoring::Vertex => PointColoring::Normal,
+            PointColoring::Normal => PointColoring::Vertex,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PointStyle {
+    /// Screen space size in pixels
+    pub size: f32,
+    /// Scale size by perspective, so that distant points are smaller
+    pub size_attenuation: bool,
+    pub shape: PointShape,
+    pub coloring: PointColoring,
+}
+
+impl Default for PointStyle {
+    fn default() -> Self {
+        PointStyle {
+            size: 3.0,
+            size_attenuation: true,
+            shape: PointShape::Round,
+            coloring: PointColoring::Vertex,
+        }
+    }
+}
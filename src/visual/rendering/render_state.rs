@@ -0,0 +1,70 @@
+use crate::visual::color::color::Color;
+
+use super::{
+    compare_function::CompareFunction, material::Material, stencil::stencil_state::StencilState,
+    transparency::blend_mode::BlendMode,
+};
+
+/// Fixed function state of a draw, applied to the fragments which passed the depth test
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderState {
+    pub blend_mode: BlendMode,
+    /// Depth only draws leave the color untouched and skip shading where possible
+    pub color_write: bool,
+    pub depth_compare: CompareFunction,
+    pub depth_write: bool,
+    pub alpha_cutoff: Option<f32>,
+    /// Disabled when `None`
+    pub stencil: Option<StencilState>,
+}
+
+impl RenderState {
+    /// Fragments failing the alpha test are discarded, leaving color and depth untouched
+    pub fn passes_alpha_test(&self, fragment: Color) -> bool {
+        match self.alpha_cutoff {
+            Some(cutoff) => fragment.alpha as f32 >= cutoff * 255.0,
+            None => true,
+        }
+    }
+
+    pub fn write(&self, color: &mut Color, z_depth: &mut f32, fragment: Color, fragment_z: f32) {
+        if self.depth_write {
+            *z_depth = fragment_z;
+        }
+        if self.color_write {
+            *color = self.blend_mode.blend(fragment, *color);
+        }
+    }
+
+    /// Fragment color is needed either to be written or to decide the alpha test
+    pub fn needs_shading(&self) -> bool {
+        self.color_write || self.alpha_cutoff.is_some()
+    }
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        RenderState {
+            blend_mode: BlendMode::Opaque,
+            color_write: true,
+            depth_compare: CompareFunction::GreaterEqual,
+            depth_write: true,
+            alpha_cutoff: None,
+            stencil: None,
+        }
+    }
+}
+
+impl From<&Material> for RenderState {
+    /// Transparent surfaces don't hide what is drawn behind them afterwards
+    fn from(material: &Material) -> Self {
+        RenderState {
+            blend_mode: material.blend_mode,
+            color_write: true,
+            depth_compare: CompareFunction::GreaterEqual,
+            depth_write: !material.is_transparent(),
+            alpha_cutoff: material.alpha_cutoff,
+            stencil: None,
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    math::geometry::rect::Rect,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+    visual::rendering::{render_state::RenderState, tiling::tile_target::TileTarget},
+};
+
+use super::render_target::{RenderTarget, RenderTargetFormat, RenderTargetValue};
+
+/// Upper bound of the targets written by a single pass
+pub const MAX_RENDER_TARGETS: usize = 8;
+
+/// Values of a fragment for each target, `None` leaves the target untouched
+pub type FragmentOutputs = [Option<RenderTargetValue>; MAX_RENDER_TARGETS];
+
+/// Offscreen render targets of the same size sharing a depth plane.
+/// Fragment output `i` of a pass is written into the target `i`.
+/// There is no stencil plane, so stencil states of the draws are ignored.
+pub struct Framebuffer {
+    targets: Vec<RenderTarget>,
+    depth: PlaneBuffer<f32>,
+    clear_depth: f32,
+    screen_rect: Rect,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            targets: Vec::new(),
+            depth: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Fill(|_| f32::MIN)),
+            clear_depth: f32::MIN,
+            screen_rect: Rect::new(0, 0, width, height),
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.depth.get_width()
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.depth.get_height()
+    }
+
+    /// Returns the output index of the new target
+    pub fn add_target(&mut self, name: &str, format: RenderTargetFormat) -> Result<usize, String> {
+        if self.targets.len() == MAX_RENDER_TARGETS {
+            return Err(format!(
+                "Framebuffer can't hold more than {} targets",
+                MAX_RENDER_TARGETS
+            ));
+        }
+        if self.get_target(name).is_some() {
+            return Err(format!("Render target \"{}\" already exists", name));
+        }
+
+        self.targets.push(RenderTarget::new(
+            name,
+            self.get_width(),
+            self.get_height(),
+            format,
+        ));
+        Ok(self.targets.len() - 1)
+    }
+
+    pub fn get_targets(&self) -> &[RenderTarget] {
+        &self.targets
+    }
+
+    pub fn get_targets_mut(&mut self) -> &mut [RenderTarget] {
+        &mut self.targets
+    }
+
+    pub fn get_target(&self, name: &str) -> Option<&RenderTarget> {
+        self.targets.iter().find(|target| target.get_name() == name)
+    }
+
+    pub fn get_target_mut(&mut self, name: &str) -> Option<&mut RenderTarget> {
+        self.targets
+            .iter_mut()
+            .find(|target| target.get_name() == name)
+    }
+
+    /// Larger depth is closer to the viewer
+    pub fn get_depth_buffer(&self) -> &PlaneBuffer<f32> {
+        &self.depth
+    }
+
+    pub fn get_depth_buffer_mut(&mut self) -> &mut PlaneBuffer<f32> {
+        &mut self.depth
+    }
+
+    /// Depth the plane is cleared to by `clean`, `f32::MIN` by default.
+    /// See `DepthState::get_clear_depth`.
+    pub fn get_clear_depth(&self) -> f32 {
+        self.clear_depth
+    }
+
+    pub fn set_clear_depth(&mut self, clear_depth: f32) {
+        self.clear_depth = clear_depth;
+    }
+
+    /// Resets the targets to the default values and the depth to the clear depth
+    pub fn clean(&mut self) {
+        for target in self.targets.iter_mut() {
+            target.clean();
+        }
+        self.depth.clean_with(&self.clear_depth);
+    }
+
+    /// Color outputs are blended by the state, the rest replace the stored values.
+    /// Outputs of a different format than their target are ignored.
+    pub fn write(
+        &mut self,
+        p: (usize, usize),
+        outputs: &FragmentOutputs,
+        z_depth: f32,
+        state: &RenderState,
+    ) {
+        if state.depth_write {
+            self.depth[p] = z_depth;
+        }
+        if !state.color_write {
+            return;
+        }
+
+        for (target, output) in self.targets.iter_mut().zip(outputs.iter()) {
+            let value = match (*output, target.get_value(p)) {
+                (Some(RenderTargetValue::Color(color)), RenderTargetValue::Color(stored)) => {
+                    RenderTargetValue::Color(state.blend_mode.blend(color, stored))
+                }
+                (Some(value), _) => value,
+                (None, _) => continue,
+            };
+            target.write(p, value);
+        }
+    }
+
+    fn to_local_rect(&self, rect: &Rect) -> Rect {
+        Rect {
+            x: rect.x - self.screen_rect.x,
+            y: rect.y - self.screen_rect.y,
+            size: rect.size,
+        }
+    }
+}
+
+impl TileTarget for Framebuffer {
+    fn get_screen_rect(&self) -> Rect {
+        self.screen_rect
+    }
+
+    fn get_sample_radius(&self) -> f32 {
+        0.0
+    }
+
+    fn copy_tile(&self, tile: &Rect) -> Framebuffer {
+        let local_tile = self.to_local_rect(tile);
+        Framebuffer {
+            targets: self
+                .targets
+                .iter()
+                .map(|target| target.copy_region(&local_tile))
+                .collect(),
+            depth: self.depth.copy_region(&local_tile),
+            clear_depth: self.clear_depth,
+            screen_rect: *tile,
+        }
+    }
+
+    fn paste_tile(&mut self, tile: &Framebuffer) {
+        let local_tile = self.to_local_rect(&tile.screen_rect);
+        for (target, tile_target) in self.targets.iter_mut().zip(tile.targets.iter()) {
+            target.paste_region(&local_tile, tile_target);
+        }
+        self.depth.paste_region(&local_tile, &tile.depth);
+    }
+}
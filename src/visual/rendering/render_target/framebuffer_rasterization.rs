@@ -0,0 +1,59 @@
+use crate::visual::{
+    rendering::{
+        render_state::RenderState,
+        render_target::render_target::RenderTargetValue,
+        shader::{
+            multi_target_shader::MultiTargetShader,
+            shader::{Fragment, ShadedFace, Varyings},
+        },
+        tiling::tile_target::TileTarget,
+        triangle::triangle_scanline::scan_triangle,
+    },
+    vertex::ScreenTriangle,
+};
+
+use super::framebuffer::{Framebuffer, MAX_RENDER_TARGETS};
+
+/// Scanline rasterization of the face into all of the framebuffer targets at once.
+/// The alpha test applies to the first output when it is a color.
+pub fn render_triangle_framebuffer<S: MultiTargetShader>(
+    face: &ShadedFace<S::Varyings>,
+    framebuffer: &mut Framebuffer,
+    shader: &S,
+    state: &RenderState,
+) {
+    let varyings = face.vertices.each_ref().map(|vertex| &vertex.varyings);
+    let screen_rect = framebuffer.get_screen_rect();
+
+    scan_triangle(
+        face.get_screen_positions(),
+        screen_rect,
+        |p, screen_pos, barycentric| {
+            if !state
+                .depth_compare
+                .compare(screen_pos.z, framebuffer.get_depth_buffer()[p])
+            {
+                return;
+            }
+
+            let outputs = if state.needs_shading() {
+                let fragment = Fragment {
+                    screen_pos,
+                    varyings: S::Varyings::interpolate(varyings, barycentric),
+                    face,
+                };
+                shader
+                    .fragment_outputs(&fragment)
+                    .filter(|outputs| match outputs[0] {
+                        Some(RenderTargetValue::Color(color)) => state.passes_alpha_test(color),
+                        _ => true,
+                    })
+            } else {
+                Some([None; MAX_RENDER_TARGETS])
+            };
+            if let Some(outputs) = outputs {
+                framebuffer.write(p, &outputs, screen_pos.z, state);
+            }
+        },
+    );
+}
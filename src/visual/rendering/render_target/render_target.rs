@@ -0,0 +1,226 @@
+use glam::Vec3A;
+
+use crate::{
+    math::geometry::rect::Rect,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+    visual::{color::color::Color, rendering::texture::texture::Texture},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderTargetFormat {
+    Color,
+    F32,
+    Vec3A,
+}
+
+/// Value of a single fragment output
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderTargetValue {
+    Color(Color),
+    F32(f32),
+    Vec3A(Vec3A),
+}
+
+#[derive(Clone, Debug)]
+enum RenderTargetPlane {
+    Color(PlaneBuffer<Color>),
+    F32(PlaneBuffer<f32>),
+    Vec3A(PlaneBuffer<Vec3A>),
+}
+
+/// Named offscreen plane written by the render passes
+#[derive(Clone, Debug)]
+pub struct RenderTarget {
+    name: String,
+    plane: RenderTargetPlane,
+}
+
+impl RenderTarget {
+    pub fn new(
+        name: &str,
+        width: usize,
+        height: usize,
+        format: RenderTargetFormat,
+    ) -> RenderTarget {
+        let plane = match format {
+            RenderTargetFormat::Color => RenderTargetPlane::Color(PlaneBuffer::new(
+                width,
+                height,
+                PlaneBufferCreateOption::Blank,
+            )),
+            RenderTargetFormat::F32 => RenderTargetPlane::F32(PlaneBuffer::new(
+                width,
+                height,
+                PlaneBufferCreateOption::Blank,
+            )),
+            RenderTargetFormat::Vec3A => RenderTargetPlane::Vec3A(PlaneBuffer::new(
+                width,
+                height,
+                PlaneBufferCreateOption::Blank,
+            )),
+        };
+        RenderTarget {
+            name: name.to_string(),
+            plane,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_format(&self) -> RenderTargetFormat {
+        match self.plane {
+            RenderTargetPlane::Color(_) => RenderTargetFormat::Color,
+            RenderTargetPlane::F32(_) => RenderTargetFormat::F32,
+            RenderTargetPlane::Vec3A(_) => RenderTargetFormat::Vec3A,
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        match &self.plane {
+            RenderTargetPlane::Color(plane) => plane.get_width(),
+            RenderTargetPlane::F32(plane) => plane.get_width(),
+            RenderTargetPlane::Vec3A(plane) => plane.get_width(),
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        match &self.plane {
+            RenderTargetPlane::Color(plane) => plane.get_height(),
+            RenderTargetPlane::F32(plane) => plane.get_height(),
+            RenderTargetPlane::Vec3A(plane) => plane.get_height(),
+        }
+    }
+
+    pub fn get_color_plane(&self) -> Option<&PlaneBuffer<Color>> {
+        match &self.plane {
+            RenderTargetPlane::Color(plane) => Some(plane),
+            _ => None,
+        }
+    }
+
+    pub fn get_f32_plane(&self) -> Option<&PlaneBuffer<f32>> {
+        match &self.plane {
+            RenderTargetPlane::F32(plane) => Some(plane),
+            _ => None,
+        }
+    }
+
+    pub fn get_vec3a_plane(&self) -> Option<&PlaneBuffer<Vec3A>> {
+        match &self.plane {
+            RenderTargetPlane::Vec3A(plane) => Some(plane),
+            _ => None,
+        }
+    }
+
+    pub fn clean(&mut self) {
+        match &mut self.plane {
+            RenderTargetPlane::Color(plane) => plane.clean(),
+            RenderTargetPlane::F32(plane) => plane.clean(),
+            RenderTargetPlane::Vec3A(plane) => plane.clean(),
+        }
+    }
+
+    /// Fills the plane with the value, values of other formats are ignored
+    pub fn clean_with(&mut self, value: RenderTargetValue) {
+        match (&mut self.plane, value) {
+            (RenderTargetPlane::Color(plane), RenderTargetValue::Color(value)) => {
+                plane.clean_with(&value)
+            }
+            (RenderTargetPlane::F32(plane), RenderTargetValue::F32(value)) => {
+                plane.clean_with(&value)
+            }
+            (RenderTargetPlane::Vec3A(plane), RenderTargetValue::Vec3A(value)) => {
+                plane.clean_with(&value)
+            }
+            _ => (),
+        }
+    }
+
+    pub fn get_value(&self, p: (usize, usize)) -> RenderTargetValue {
+        match &self.plane {
+            RenderTargetPlane::Color(plane) => RenderTargetValue::Color(plane[p]),
+            RenderTargetPlane::F32(plane) => RenderTargetValue::F32(plane[p]),
+            RenderTargetPlane::Vec3A(plane) => RenderTargetValue::Vec3A(plane[p]),
+        }
+    }
+
+    /// Values of other formats are ignored
+    pub fn write(&mut self, p: (usize, usize), value: RenderTargetValue) {
+        match (&mut self.plane, value) {
+            (RenderTargetPlane::Color(plane), RenderTargetValue::Color(value)) => plane[p] = value,
+            (RenderTargetPlane::F32(plane), RenderTargetValue::F32(value)) => plane[p] = value,
+            (RenderTargetPlane::Vec3A(plane), RenderTargetValue::Vec3A(value)) => plane[p] = value,
+            _ => (),
+        }
+    }
+
+    /// Texture with the plane contents, bottom row first as the texture coordinates go.
+    /// `F32` values are normalized by their range, skipping the cleared `f32::MIN` ones,
+    /// `Vec3A` values are mapped from -1..1 into the RGB channels as normal maps are.
+    pub fn to_texture(&self) -> Result<Texture, String> {
+        let (width, height) = (self.get_width(), self.get_height());
+        let texels = match &self.plane {
+            RenderTargetPlane::Color(plane) => {
+                collect_texels(plane, |color| [color.r, color.g, color.b, color.alpha])
+            }
+            RenderTargetPlane::F32(plane) => {
+                let (min, max) = plane
+                    .get_buffer()
+                    .iter()
+                    .filter(|value| **value != f32::MIN)
+                    .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                        (min.min(*value), max.max(*value))
+                    });
+                let scale = if max > min { 255.0 / (max - min) } else { 0.0 };
+                collect_texels(plane, |value| {
+                    let channel = ((value - min) * scale).round().clamp(0.0, 255.0) as u8;
+                    [channel, channel, channel, 255]
+                })
+            }
+            RenderTargetPlane::Vec3A(plane) => collect_texels(plane, |vector| {
+                let rgb = ((vector * 0.5 + 0.5) * 255.0)
+                    .round()
+                    .clamp(Vec3A::ZERO, Vec3A::splat(255.0));
+                [rgb.x as u8, rgb.y as u8, rgb.z as u8, 255]
+            }),
+        };
+        Texture::from_texels(width, height, texels)
+    }
+
+    pub fn copy_region(&self, region: &Rect) -> RenderTarget {
+        let plane = match &self.plane {
+            RenderTargetPlane::Color(plane) => RenderTargetPlane::Color(plane.copy_region(region)),
+            RenderTargetPlane::F32(plane) => RenderTargetPlane::F32(plane.copy_region(region)),
+            RenderTargetPlane::Vec3A(plane) => RenderTargetPlane::Vec3A(plane.copy_region(region)),
+        };
+        RenderTarget {
+            name: self.name.clone(),
+            plane,
+        }
+    }
+
+    pub fn paste_region(&mut self, region: &Rect, source: &RenderTarget) {
+        match (&mut self.plane, &source.plane) {
+            (RenderTargetPlane::Color(plane), RenderTargetPlane::Color(source)) => {
+                plane.paste_region(region, source)
+            }
+            (RenderTargetPlane::F32(plane), RenderTargetPlane::F32(source)) => {
+                plane.paste_region(region, source)
+            }
+            (RenderTargetPlane::Vec3A(plane), RenderTargetPlane::Vec3A(source)) => {
+                plane.paste_region(region, source)
+            }
+            _ => (),
+        }
+    }
+}
+
+fn collect_texels<T: Copy>(plane: &PlaneBuffer<T>, texel: impl Fn(T) -> [u8; 4]) -> Vec<[u8; 4]> {
+    let (width, height) = (plane.get_width(), plane.get_height());
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|p| texel(plane[p]))
+        .collect()
+}
@@ -0,0 +1,318 @@
+use glam::{Mat2, Mat3A, Mat4, Vec2, Vec3A, Vec4};
+
+use crate::{
+    derive_varyings,
+    math::{
+        geometry::apply_transform_matrix::{
+            vector_apply_transform_matrix, vertex_apply_transform_matrix,
+        },
+        lanes::{Vec3x4, LANES},
+    },
+    visual::{
+        color::color::Color,
+        rendering::{
+            light_source::LightSource,
+            material::{PbrMaterial, ShadingModel, SpecularMaterial},
+            matrix::{camera_matrixes::CameraMatrixes, projection_matrix::get_projection_eye},
+            render_target::{
+                framebuffer::{FragmentOutputs, MAX_RENDER_TARGETS},
+                render_target::RenderTargetValue,
+            },
+            texture::{sampler::Sampler, texture::Texture},
+            wavefront_obj::wavefront_render_model::WavefrontRenderModel,
+        },
+        vertex::ScreenTriangle,
+    },
+};
+
+use super::{
+    metallic_roughness::MetallicRoughness,
+    multi_target_shader::MultiTargetShader,
+    shader::{Fragment, FragmentLanes, ShadedFace, Shader, VertexInput, VertexOutput},
+    surface::{Specular, SpecularLanes, Surface, SurfaceLanes, SurfaceShader},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct DefaultVaryings {
+    pub uv: Vec2,
+    pub normal: Vec3A,
+    /// Position in the lighting space, before the projection
+    pub position: Vec3A,
+}
+
+derive_varyings!(DefaultVaryings, uv, normal, position);
+
+/// Diffuse texture lit by the light sources, with optional tangent space normal map,
+/// specular map, glow map, metallic-roughness and occlusion maps and shadow maps
+pub struct DefaultShader<'a> {
+    texture: &'a Texture,
+    normal_map: Option<&'a Texture>,
+    spec_map: Option<&'a Texture>,
+    glow_map: Option<&'a Texture>,
+    metallic_roughness_map: Option<&'a Texture>,
+    occlusion_map: Option<&'a Texture>,
+    sampler: Sampler,
+    opacity: f32,
+    shading_model: ShadingModel,
+    specular: SpecularMaterial,
+    pbr: PbrMaterial,
+    lights: &'a [LightSource],
+    eye: Vec4,
+    world_matrix: Mat4,
+    transform_matrix: Mat4,
+    normal_matrix: Mat4,
+    double_sided_lighting: bool,
+    receives_shadows: bool,
+}
+
+impl<'a> DefaultShader<'a> {
+    /// Light directions are expected to be in the same (rotated) space as normals
+    pub fn new(
+        model: &'a WavefrontRenderModel,
+        lights: &'a [LightSource],
+        camera: &CameraMatrixes,
+    ) -> DefaultShader<'a> {
+        let wavefront_obj = &model.obj;
+        let world_matrix = model.model_matrix * camera.rotation_matrix * camera.view_matrix;
+        DefaultShader {
+            texture: &wavefront_obj.texture,
+            normal_map: wavefront_obj
+                .normal_map
+                .as_ref()
+                .filter(|_| model.use_normal_map),
+            spec_map: wavefront_obj
+                .spec_map
+                .as_ref()
+                .filter(|_| model.use_spec_map),
+            glow_map: wavefront_obj
+                .glow_map
+                .as_ref()
+                .filter(|_| model.use_glow_map),
+            metallic_roughness_map: wavefront_obj.metallic_roughness_map.as_ref(),
+            occlusion_map: wavefront_obj.occlusion_map.as_ref(),
+            sampler: model.material.sampler,
+            opacity: model.material.opacity,
+            shading_model: model.material.shading_model,
+            specular: model.material.specular,
+            pbr: model.material.pbr,
+            lights,
+            eye: get_projection_eye(camera.projection),
+            world_matrix,
+            transform_matrix: camera.projection * world_matrix,
+            normal_matrix: camera.get_normal_matrix(),
+            double_sided_lighting: model.double_sided_lighting,
+            receives_shadows: model.receives_shadows,
+        }
+    }
+
+    /// Surfaces of the diffuse and specular shading model for the fragment lanes
+    fn surface_lanes(&self, lanes: &FragmentLanes<DefaultVaryings>) -> SurfaceLanes {
+        let face = lanes.face;
+        let varyings = face.vertices.map(|v| v.varyings);
+        let weights = &lanes.weights;
+        let us = weights[0] * varyings[0].uv.x
+            + weights[1] * varyings[1].uv.x
+            + weights[2] * varyings[2].uv.x;
+        let vs = weights[0] * varyings[0].uv.y
+            + weights[1] * varyings[1].uv.y
+            + weights[2] * varyings[2].uv.y;
+        let (duv_dx, duv_dy) = face.derivatives(|varyings| varyings.uv);
+        let sample = |texture: &Texture, i: usize| {
+            self.sampler
+                .sample(texture, Vec2::new(us[i], vs[i]), duv_dx, duv_dy)
+        };
+        let sample_lanes = |texture: &Texture, scale: f32| {
+            let mut texels = Vec3x4::ZERO;
+            for i in lanes.lanes() {
+                texels.set_lane(i, Vec3A::from(sample(texture, i).truncate()) * scale);
+            }
+            texels
+        };
+
+        let mut normal = Vec3x4::interpolate(varyings.map(|v| v.normal), weights);
+        if !face.is_front_facing && self.double_sided_lighting {
+            normal = Vec3x4::ZERO - normal;
+        }
+
+        if let Some(normal_map) = self.normal_map {
+            let (i, j) = get_tangent_basis(face);
+            // Normals are encoded into 0..1 range of the RGB channels
+            let mapped = sample_lanes(normal_map, 2.0) - Vec3x4::splat(Vec3A::ONE);
+            normal = (Vec3x4::splat(i).scale(mapped.x)
+                + Vec3x4::splat(j).scale(mapped.y)
+                + normal.scale(mapped.z))
+            .normalize_or_zero();
+        }
+
+        let emissive = self.glow_map.map_or(Vec3x4::ZERO, |glow_map| {
+            sample_lanes(glow_map, 255.0 / 128.0)
+        });
+
+        let shininess = match self.spec_map {
+            Some(spec_map) => {
+                let mut shininess = Vec4::ZERO;
+                for i in lanes.lanes() {
+                    shininess[i] = self.specular.map_shininess(sample(spec_map, i));
+                }
+                Some(shininess)
+            }
+            None => self.specular.shininess.map(Vec4::splat),
+        };
+        let specular = shininess.map(|shininess| SpecularLanes {
+            model: self.specular.model,
+            color: self.specular.color,
+            shininess,
+            energy_conservation: self.specular.energy_conservation,
+        });
+
+        let mut albedo = [Color::default(); LANES];
+        for i in lanes.lanes() {
+            let mut texture_color = sample(self.texture, i);
+            texture_color.w *= self.opacity;
+            albedo[i] = Color::from_vec4(texture_color);
+        }
+
+        SurfaceLanes {
+            albedo,
+            position: Vec3x4::interpolate(varyings.map(|v| v.position), weights),
+            normal,
+            specular,
+            emissive,
+            receives_shadows: self.receives_shadows,
+            mask: lanes.mask,
+        }
+    }
+}
+
+/// Unit tangent and bitangent of the face from its screen space edges and their
+/// texture coordinates, they lie in the screen plane and are the same for all its fragments
+fn get_tangent_basis(face: &ShadedFace<DefaultVaryings>) -> (Vec3A, Vec3A) {
+    let [p0, p1, p2] = face.get_screen_positions();
+    let [uv0, uv1, uv2] = face.vertices.map(|v| v.varyings.uv);
+    let (e1, e2) = (p1 - p0, p2 - p0);
+
+    let AI = Mat2::from_cols(e1.truncate(), e2.truncate())
+        .transpose()
+        .inverse();
+    let i = AI * Vec2::new(uv1.x - uv0.x, uv2.x - uv0.x);
+    let j = AI * Vec2::new(uv1.y - uv0.y, uv2.y - uv0.y);
+    (
+        Vec3A::from((i.normalize(), 0.0)),
+        Vec3A::from((j.normalize(), 0.0)),
+    )
+}
+
+impl Shader for DefaultShader<'_> {
+    type Varyings = DefaultVaryings;
+
+    fn vertex(&self, input: &VertexInput) -> VertexOutput<DefaultVaryings> {
+        VertexOutput {
+            clip_pos: self.transform_matrix * Vec4::from((input.position, 1.0)),
+            varyings: DefaultVaryings {
+                uv: input.uv,
+                normal: vector_apply_transform_matrix(input.normal, self.normal_matrix).normalize(),
+                position: vertex_apply_transform_matrix(input.position, self.world_matrix),
+            },
+        }
+    }
+
+    fn fragment(&self, fragment: &Fragment<DefaultVaryings>) -> Option<Color> {
+        self.surface(fragment)
+            .map(|surface| surface.shade(self.eye, self.lights))
+    }
+
+    fn fragment_lanes(&self, lanes: &FragmentLanes<DefaultVaryings>) -> [Option<Color>; LANES] {
+        match self.shading_model {
+            ShadingModel::DiffuseSpecular => self.surface_lanes(lanes).shade(self.eye, self.lights),
+            // The physically based model is only lit per fragment
+            ShadingModel::MetallicRoughness => lanes.map_lanes(|fragment| self.fragment(fragment)),
+        }
+    }
+}
+
+impl SurfaceShader for DefaultShader<'_> {
+    fn surface(&self, fragment: &Fragment<DefaultVaryings>) -> Option<Surface> {
+        let DefaultVaryings {
+            uv,
+            mut normal,
+            position,
+        } = fragment.varyings;
+        let face = fragment.face;
+        let (duv_dx, duv_dy) = fragment.derivatives(|varyings| varyings.uv);
+        let sample = |texture: &Texture| self.sampler.sample(texture, uv, duv_dx, duv_dy);
+
+        if !face.is_front_facing && self.double_sided_lighting {
+            normal = -normal;
+        }
+
+        if let Some(normal_map) = self.normal_map {
+            let (i, j) = get_tangent_basis(face);
+            let B = Mat3A::from_cols(i, j, normal);
+            // Normals are encoded into 0..1 range of the RGB channels
+            let mapped_normal = Vec3A::from(sample(normal_map).truncate()) * 2.0 - 1.0;
+            normal = (B * mapped_normal).normalize();
+        }
+
+        let mut emissive = self.glow_map.map_or(Vec3A::ZERO, |glow_map| {
+            Vec3A::from(sample(glow_map).truncate()) * (255.0 / 128.0)
+        });
+
+        let metallic_roughness = match self.shading_model {
+            ShadingModel::DiffuseSpecular => None,
+            ShadingModel::MetallicRoughness => {
+                let (mut metallic, mut roughness) = (self.pbr.metallic, self.pbr.roughness);
+                if let Some(metallic_roughness_map) = self.metallic_roughness_map {
+                    let texel = sample(metallic_roughness_map);
+                    roughness *= texel.y;
+                    metallic *= texel.z;
+                }
+                let occlusion = self.occlusion_map.map_or(1.0, |occlusion_map| {
+                    1.0 + (sample(occlusion_map).x - 1.0) * self.pbr.occlusion_strength
+                });
+                emissive += self.pbr.emissive;
+                Some(MetallicRoughness {
+                    metallic,
+                    roughness,
+                    occlusion,
+                })
+            }
+        };
+
+        let shininess = match self.spec_map {
+            Some(spec_map) => Some(self.specular.map_shininess(sample(spec_map))),
+            None => self.specular.shininess,
+        };
+        let specular = shininess.map(|shininess| Specular {
+            model: self.specular.model,
+            color: self.specular.color,
+            shininess,
+            energy_conservation: self.specular.energy_conservation,
+        });
+
+        let mut texture_color = sample(self.texture);
+        texture_color.w *= self.opacity;
+        Some(Surface {
+            albedo: Color::from_vec4(texture_color),
+            position,
+            normal,
+            specular,
+            emissive,
+            metallic_roughness,
+            receives_shadows: self.receives_shadows,
+        })
+    }
+}
+
+impl MultiTargetShader for DefaultShader<'_> {
+    /// Lit color into the first target and the shading normal into the second one
+    fn fragment_outputs(&self, fragment: &Fragment<DefaultVaryings>) -> Option<FragmentOutputs> {
+        self.surface(fragment).map(|surface| {
+            let mut outputs = [None; MAX_RENDER_TARGETS];
+            outputs[0] = Some(RenderTargetValue::Color(
+                surface.shade(self.eye, self.lights),
+            ));
+            outputs[1] = Some(RenderTargetValue::Vec3A(surface.normal));
+            outputs
+        })
+    }
+}
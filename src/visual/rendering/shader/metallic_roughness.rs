@@ -0,0 +1,65 @@
+use std::f32::consts::PI;
+
+use glam::Vec3A;
+
+/// Reflectance of the dielectrics at normal incidence
+const DIELECTRIC_F0: f32 = 0.04;
+
+/// Inputs of the Cook-Torrance BRDF of the metallic-roughness workflow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MetallicRoughness {
+    pub metallic: f32,
+    /// Perceptual roughness, squared into the GGX alpha
+    pub roughness: f32,
+    /// Ambient occlusion, scales the ambient light
+    pub occlusion: f32,
+}
+
+impl MetallicRoughness {
+    /// Light reflected towards the eye for the linear base color and unit normal, light and view
+    /// directions, including the incidence cosine. Lights carry the factor of π, so a white
+    /// lambertian surface reflects the light spectrum at normal incidence.
+    pub fn calculate(
+        &self,
+        base_color: Vec3A,
+        normal: Vec3A,
+        to_light: Vec3A,
+        to_eye: Vec3A,
+    ) -> Vec3A {
+        let n_dot_l = normal.dot(to_light);
+        if n_dot_l <= 0.0 {
+            return Vec3A::ZERO;
+        }
+        let n_dot_v = normal.dot(to_eye).max(1e-4);
+        let half = (to_light + to_eye).normalize_or_zero();
+        let n_dot_h = normal.dot(half).max(0.0);
+        let v_dot_h = to_eye.dot(half).max(0.0);
+
+        // Perfectly smooth surfaces would reflect the punctual lights into a single point
+        let roughness = self.roughness.clamp(0.04, 1.0);
+        let metallic = self.metallic.clamp(0.0, 1.0);
+
+        let f0 = Vec3A::splat(DIELECTRIC_F0).lerp(base_color, metallic);
+        let fresnel = f0 + (Vec3A::ONE - f0) * (1.0 - v_dot_h).powi(5);
+        let distribution = ggx_distribution(n_dot_h, roughness * roughness);
+        let geometry = smith_geometry(n_dot_v, n_dot_l, roughness);
+
+        let specular = fresnel * (distribution * geometry / (4.0 * n_dot_v * n_dot_l));
+        let diffuse = (Vec3A::ONE - fresnel) * (1.0 - metallic) * base_color / PI;
+        (diffuse + specular) * (PI * n_dot_l)
+    }
+}
+
+// Trowbridge-Reitz normal distribution
+fn ggx_distribution(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * d * d)
+}
+
+// Smith masking and shadowing with the Schlick-GGX term remapped for the punctual lights
+fn smith_geometry(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    let schlick_ggx = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    schlick_ggx(n_dot_v) * schlick_ggx(n_dot_l)
+}
@@ -0,0 +1,19 @@
+use crate::visual::rendering::render_target::{
+    framebuffer::{FragmentOutputs, MAX_RENDER_TARGETS},
+    render_target::RenderTargetValue,
+};
+
+use super::shader::{Fragment, Shader};
+
+/// Shader writing several values per fragment, one into each framebuffer target
+pub trait MultiTargetShader: Shader {
+    /// Returns `None` to discard the fragment.
+    /// By default the fragment color goes into the first target.
+    fn fragment_outputs(&self, fragment: &Fragment<Self::Varyings>) -> Option<FragmentOutputs> {
+        self.fragment(fragment).map(|color| {
+            let mut outputs = [None; MAX_RENDER_TARGETS];
+            outputs[0] = Some(RenderTargetValue::Color(color));
+            outputs
+        })
+    }
+}
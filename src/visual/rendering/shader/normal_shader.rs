@@ -0,0 +1,55 @@
+use glam::{Mat4, Vec3A, Vec4};
+
+use crate::{
+    math::geometry::apply_transform_matrix::vector_apply_transform_matrix,
+    visual::{
+        color::color::Color,
+        rendering::{
+            matrix::camera_matrixes::CameraMatrixes,
+            wavefront_obj::wavefront_render_model::WavefrontRenderModel,
+        },
+    },
+};
+
+use super::{
+    multi_target_shader::MultiTargetShader,
+    shader::{Fragment, Shader, VertexInput, VertexOutput},
+};
+
+/// Debug shader mapping interpolated normals into RGB
+pub struct NormalShader {
+    transform_matrix: Mat4,
+    normal_matrix: Mat4,
+    opacity: f32,
+}
+
+impl NormalShader {
+    pub fn new(model: &WavefrontRenderModel, camera: &CameraMatrixes) -> NormalShader {
+        NormalShader {
+            transform_matrix: camera.projection
+                * model.model_matrix
+                * camera.rotation_matrix
+                * camera.view_matrix,
+            normal_matrix: camera.get_normal_matrix(),
+            opacity: model.material.opacity,
+        }
+    }
+}
+
+impl Shader for NormalShader {
+    type Varyings = Vec3A;
+
+    fn vertex(&self, input: &VertexInput) -> VertexOutput<Vec3A> {
+        VertexOutput {
+            clip_pos: self.transform_matrix * Vec4::from((input.position, 1.0)),
+            varyings: vector_apply_transform_matrix(input.normal, self.normal_matrix),
+        }
+    }
+
+    fn fragment(&self, fragment: &Fragment<Vec3A>) -> Option<Color> {
+        let color = fragment.varyings.normalize() * 0.5 + 0.5;
+        Some(Color::from_vec4(Vec4::from((color, self.opacity))))
+    }
+}
+
+impl MultiTargetShader for NormalShader {}
@@ -0,0 +1,54 @@
+use glam::{Mat4, Vec4};
+
+use crate::visual::{
+    color::color::Color,
+    rendering::{
+        matrix::camera_matrixes::CameraMatrixes,
+        wavefront_obj::wavefront_render_model::WavefrontRenderModel,
+    },
+};
+
+use super::shader::{Fragment, Shader, VertexInput, VertexOutput};
+
+/// Flat colored model inflated along the vertex normals,
+/// drawn around the stencil mask of the model it makes an outline
+pub struct OutlineShader {
+    transform_matrix: Mat4,
+    width: f32,
+    color: Color,
+}
+
+impl OutlineShader {
+    /// Width is in the model space units
+    pub fn new(
+        model: &WavefrontRenderModel,
+        camera: &CameraMatrixes,
+        width: f32,
+        color: Color,
+    ) -> OutlineShader {
+        OutlineShader {
+            transform_matrix: camera.projection
+                * model.model_matrix
+                * camera.rotation_matrix
+                * camera.view_matrix,
+            width,
+            color,
+        }
+    }
+}
+
+impl Shader for OutlineShader {
+    type Varyings = ();
+
+    fn vertex(&self, input: &VertexInput) -> VertexOutput<()> {
+        let position = input.position + input.normal.normalize_or_zero() * self.width;
+        VertexOutput {
+            clip_pos: self.transform_matrix * Vec4::from((position, 1.0)),
+            varyings: (),
+        }
+    }
+
+    fn fragment(&self, _: &Fragment<()>) -> Option<Color> {
+        Some(self.color)
+    }
+}
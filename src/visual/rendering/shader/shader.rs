@@ -0,0 +1,177 @@
+use glam::{Vec2, Vec3A, Vec4};
+
+use crate::{
+    math::lanes::LANES,
+    visual::{color::color::Color, vertex::ScreenTriangle},
+};
+
+/// Per-vertex attributes of the model, in model space
+#[derive(Clone, Copy, Debug)]
+pub struct VertexInput {
+    pub position: Vec3A,
+    pub uv: Vec2,
+    pub normal: Vec3A,
+}
+
+pub struct VertexOutput<V> {
+    /// Position before the perspective division and viewport transform
+    pub clip_pos: Vec4,
+    pub varyings: V,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShadedVertex<V> {
+    pub screen_pos: Vec3A,
+    pub varyings: V,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShadedFace<V> {
+    pub vertices: [ShadedVertex<V>; 3],
+    pub is_front_facing: bool,
+}
+
+impl<V> ScreenTriangle for ShadedFace<V> {
+    fn get_screen_positions(&self) -> [Vec3A; 3] {
+        [
+            self.vertices[0].screen_pos,
+            self.vertices[1].screen_pos,
+            self.vertices[2].screen_pos,
+        ]
+    }
+}
+
+pub struct Fragment<'a, V> {
+    /// Pixel center coordinates along with the interpolated depth
+    pub screen_pos: Vec3A,
+    pub varyings: V,
+    pub face: &'a ShadedFace<V>,
+}
+
+impl<V> Fragment<'_, V> {
+    /// Screen space derivatives (d/dx, d/dy) of a value interpolated across the face.
+    /// Interpolation is affine in screen space, so they are constant over the face.
+    pub fn derivatives(&self, value: impl Fn(&V) -> Vec2) -> (Vec2, Vec2) {
+        self.face.derivatives(value)
+    }
+}
+
+impl<V> ShadedFace<V> {
+    /// Screen space derivatives (d/dx, d/dy) of a value interpolated across the face
+    pub fn derivatives(&self, value: impl Fn(&V) -> Vec2) -> (Vec2, Vec2) {
+        let [p0, p1, p2] = self.get_screen_positions();
+        let [v0, v1, v2] = [0, 1, 2].map(|i| value(&self.vertices[i].varyings));
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let (dv1, dv2) = (v1 - v0, v2 - v0);
+
+        let det = e1.x * e2.y - e2.x * e1.y;
+        if det == 0.0 {
+            return (Vec2::ZERO, Vec2::ZERO);
+        }
+        (
+            (dv1 * e2.y - dv2 * e1.y) / det,
+            (dv2 * e1.x - dv1 * e2.x) / det,
+        )
+    }
+}
+
+/// Fragments of up to `LANES` adjacent pixels of a row, in structure-of-arrays layout
+pub struct FragmentLanes<'a, V> {
+    /// Pixel center coordinates of the lanes
+    pub xs: Vec4,
+    pub y: f32,
+    pub z_depth: Vec4,
+    /// Barycentric weights of the face vertices in each lane
+    pub weights: [Vec4; 3],
+    /// Lane `i` is shaded when bit `i` is set
+    pub mask: u32,
+    pub face: &'a ShadedFace<V>,
+}
+
+impl<'a, V: Varyings> FragmentLanes<'a, V> {
+    pub fn lanes(&self) -> impl Iterator<Item = usize> {
+        let mask = self.mask;
+        (0..LANES).filter(move |i| mask & (1 << i) != 0)
+    }
+
+    pub fn fragment(&self, lane: usize) -> Fragment<'a, V> {
+        let weights = Vec3A::new(
+            self.weights[0][lane],
+            self.weights[1][lane],
+            self.weights[2][lane],
+        );
+        Fragment {
+            screen_pos: Vec3A::new(self.xs[lane], self.y, self.z_depth[lane]),
+            varyings: V::interpolate(self.face.vertices.each_ref().map(|v| &v.varyings), weights),
+            face: self.face,
+        }
+    }
+
+    /// Shades the lanes of the mask one by one
+    pub fn map_lanes(
+        &self,
+        shade: impl Fn(&Fragment<V>) -> Option<Color>,
+    ) -> [Option<Color>; LANES] {
+        let mut colors = [None; LANES];
+        for i in self.lanes() {
+            colors[i] = shade(&self.fragment(i));
+        }
+        colors
+    }
+}
+
+/// Values passed from the vertex stage to the fragment stage,
+/// interpolated across the triangle by barycentric weights.
+/// Structs of interpolable fields can implement it with `derive_varyings!`.
+pub trait Varyings: Copy + Send + Sync {
+    fn interpolate(values: [&Self; 3], weights: Vec3A) -> Self;
+}
+
+pub trait Shader: Sync {
+    type Varyings: Varyings;
+
+    fn vertex(&self, input: &VertexInput) -> VertexOutput<Self::Varyings>;
+
+    /// Returns `None` to discard the fragment, leaving color and depth untouched
+    fn fragment(&self, fragment: &Fragment<Self::Varyings>) -> Option<Color>;
+
+    /// Colors of the masked lanes for the edge function rasterizer, other lanes are ignored.
+    /// Runs `fragment` for each lane by default, shaders may override it to keep
+    /// the work in SIMD lanes.
+    fn fragment_lanes(&self, lanes: &FragmentLanes<Self::Varyings>) -> [Option<Color>; LANES] {
+        lanes.map_lanes(|fragment| self.fragment(fragment))
+    }
+}
+
+impl Varyings for () {
+    fn interpolate(_: [&Self; 3], _: Vec3A) -> Self {}
+}
+
+macro_rules! impl_varyings_for_values {
+    ($($t:ty),+) => {
+        $(
+            impl Varyings for $t {
+                fn interpolate(values: [&Self; 3], weights: Vec3A) -> Self {
+                    *values[0] * weights.x + *values[1] * weights.y + *values[2] * weights.z
+                }
+            }
+        )+
+    };
+}
+
+impl_varyings_for_values!(f32, Vec2, Vec3A, Vec4);
+
+#[macro_export]
+macro_rules! derive_varyings {
+    ($t:ident, $($field:tt),+) => {
+        impl $crate::visual::rendering::shader::shader::Varyings for $t {
+            fn interpolate(values: [&Self; 3], weights: glam::Vec3A) -> Self {
+                $t {
+                    $($field: values[0].$field * weights.x
+                        + values[1].$field * weights.y
+                        + values[2].$field * weights.z,)+
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,480 @@
+use glam::{Vec3A, Vec4};
+
+use crate::{
+    math::lanes::{powf_lanes, Vec3x4, LANES},
+    visual::{
+        color::color::Color,
+        rendering::{
+            light_source::{LightSource, LightSourceKind},
+            material::SpecularModel,
+            shadow::{shadow_map::ShadowMap, shadow_settings::ShadowSettings},
+        },
+    },
+};
+
+use super::{
+    metallic_roughness::MetallicRoughness,
+    shader::{Fragment, Shader},
+};
+
+/// Attributes of a fragment needed for its lighting
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Surface {
+    pub albedo: Color,
+    /// Position in the same (rotated) space as the light sources
+    pub position: Vec3A,
+    /// Unit normal in the same (rotated) space as the light directions
+    pub normal: Vec3A,
+    /// The surface has no highlight without it
+    pub specular: Option<Specular>,
+    /// Intensities emitted by the surface itself, added to the lighting
+    pub emissive: Vec3A,
+    /// Lit by the physically based model instead of the diffuse and specular terms,
+    /// the albedo is then its base color
+    pub metallic_roughness: Option<MetallicRoughness>,
+    /// Shadow maps of the lights are ignored without it
+    pub receives_shadows: bool,
+}
+
+/// Specular highlight of the fragment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Specular {
+    pub model: SpecularModel,
+    pub color: Vec3A,
+    pub shininess: f32,
+    pub energy_conservation: bool,
+}
+
+impl Specular {
+    /// Highlight intensity for unit normal, light and view directions
+    fn calculate(&self, normal: Vec3A, to_light: Vec3A, to_eye: Vec3A) -> f32 {
+        let (cos, normalization) = match self.model {
+            SpecularModel::Phong => {
+                let reflection = normal * (normal.dot(to_light) * 2.0) - to_light;
+                (reflection.dot(to_eye), (self.shininess + 2.0) / 2.0)
+            }
+            SpecularModel::BlinnPhong => {
+                let half = (to_light + to_eye).normalize_or_zero();
+                (normal.dot(half), (self.shininess + 8.0) / 8.0)
+            }
+        };
+        let spec = cos.max(0.0).powf(self.shininess);
+        if self.energy_conservation {
+            spec * normalization
+        } else {
+            spec
+        }
+    }
+}
+
+impl Surface {
+    /// Lit color of the surface, the eye is the homogeneous viewer position in the lighting space
+    pub fn shade(&self, eye: Vec4, lights: &[LightSource]) -> Color {
+        let to_eye = (Vec3A::from(eye) - self.position * eye.w).normalize_or_zero();
+        match self.metallic_roughness {
+            Some(metallic_roughness) => {
+                self.shade_metallic_roughness(&metallic_roughness, to_eye, lights)
+            }
+            None => self.shade_diffuse_specular(to_eye, lights),
+        }
+    }
+
+    fn shade_diffuse_specular(&self, to_eye: Vec3A, lights: &[LightSource]) -> Color {
+        let mut intensities = self.emissive;
+        let mut highlights = Vec3A::ZERO;
+
+        for light in lights.iter() {
+            let (dir, attenuation) = match self.get_incidence(light) {
+                Incidence::Ambient => {
+                    intensities += light.spectrum;
+                    continue;
+                }
+                Incidence::Directed { dir, attenuation } => (dir, attenuation),
+            };
+
+            if attenuation <= 0.0 {
+                continue;
+            }
+
+            let n_dot_l = self.normal.dot(dir);
+
+            let mut diffuse = light.spectrum * n_dot_l.max(0.0).powf(light.concentration);
+            if let Some(specular) = self.specular {
+                if n_dot_l > 0.0 {
+                    let spec = specular.calculate(self.normal, dir, to_eye);
+                    highlights += light.spectrum * specular.color * (spec * n_dot_l * attenuation);
+                }
+                if specular.energy_conservation {
+                    diffuse *= Vec3A::ONE - specular.color.min(Vec3A::ONE);
+                }
+            }
+            intensities += diffuse * attenuation;
+        }
+
+        self.albedo
+            .apply_intensity(intensities)
+            .add_intensity(highlights)
+    }
+
+    // Lighting is done in linear color space, the albedo is the sRGB encoded base color
+    fn shade_metallic_roughness(
+        &self,
+        metallic_roughness: &MetallicRoughness,
+        to_eye: Vec3A,
+        lights: &[LightSource],
+    ) -> Color {
+        let base_color = self.albedo.to_linear();
+        let base_rgb = Vec3A::from(base_color);
+        let mut radiance = self.emissive;
+
+        for light in lights.iter() {
+            let (dir, attenuation) = match self.get_incidence(light) {
+                Incidence::Ambient => {
+                    radiance += light.spectrum * base_rgb * metallic_roughness.occlusion;
+                    continue;
+                }
+                Incidence::Directed { dir, attenuation } => (dir, attenuation),
+            };
+
+            if attenuation <= 0.0 {
+                continue;
+            }
+
+            radiance += metallic_roughness.calculate(base_rgb, self.normal, dir, to_eye)
+                * light.spectrum
+                * attenuation;
+        }
+
+        Color::from_linear(Vec4::from((radiance, base_color.w)))
+    }
+
+    fn get_incidence(&self, light: &LightSource) -> Incidence {
+        match &light.kind {
+            LightSourceKind::Linear {
+                dir,
+                shadow,
+                shadow_maps,
+            } => {
+                // First cascade reaching as far as the position
+                let shadow_map = shadow_maps
+                    .iter()
+                    .find(|shadow_map| self.position.z >= shadow_map.min_z);
+                Incidence::Directed {
+                    dir: *dir,
+                    attenuation: self.get_shadow(shadow, shadow_map, *dir),
+                }
+            }
+            LightSourceKind::Point {
+                position,
+                attenuation,
+                shadow,
+                shadow_map,
+            } => {
+                let to_light = *position - self.position;
+                let distance = to_light.length();
+                let to_light = to_light.normalize_or_zero();
+                let self_shadow = match shadow_map {
+                    Some(shadow_map) if self.receives_shadows => {
+                        shadow.calculate(shadow_map, self.position, self.normal, to_light)
+                    }
+                    _ => 1.0,
+                };
+                Incidence::Directed {
+                    dir: to_light,
+                    attenuation: attenuation.calculate(distance) * self_shadow,
+                }
+            }
+            LightSourceKind::Spot {
+                position,
+                dir,
+                cone,
+                attenuation,
+                shadow,
+                shadow_map,
+            } => {
+                let to_light = *position - self.position;
+                let distance = to_light.length();
+                let to_light = to_light.normalize_or_zero();
+                let self_shadow = self.get_shadow(shadow, shadow_map.as_ref(), to_light);
+                let cone_falloff = cone.calculate(-to_light.dot(dir.normalize()));
+                Incidence::Directed {
+                    dir: to_light,
+                    attenuation: attenuation.calculate(distance) * cone_falloff * self_shadow,
+                }
+            }
+            LightSourceKind::Ambient => Incidence::Ambient,
+        }
+    }
+
+    fn get_shadow(
+        &self,
+        shadow: &ShadowSettings,
+        shadow_map: Option<&ShadowMap>,
+        to_light: Vec3A,
+    ) -> f32 {
+        match shadow_map {
+            Some(shadow_map) if self.receives_shadows => {
+                shadow.calculate(shadow_map, self.position, self.normal, to_light)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+enum Incidence {
+    Ambient,
+    /// Direction towards the light and the fraction of its intensity reaching the surface
+    Directed {
+        dir: Vec3A,
+        attenuation: f32,
+    },
+}
+
+/// Surfaces of adjacent fragments in structure-of-arrays layout,
+/// lit by the diffuse and specular terms only
+#[derive(Clone, Copy, Debug)]
+pub struct SurfaceLanes {
+    pub albedo: [Color; LANES],
+    pub position: Vec3x4,
+    pub normal: Vec3x4,
+    pub specular: Option<SpecularLanes>,
+    pub emissive: Vec3x4,
+    pub receives_shadows: bool,
+    /// Lane `i` is shaded when bit `i` is set
+    pub mask: u32,
+}
+
+/// Specular highlight of the fragment lanes, the shininess may vary per lane
+#[derive(Clone, Copy, Debug)]
+pub struct SpecularLanes {
+    pub model: SpecularModel,
+    pub color: Vec3A,
+    pub shininess: Vec4,
+    pub energy_conservation: bool,
+}
+
+impl SpecularLanes {
+    fn calculate(&self, normal: &Vec3x4, to_light: &Vec3x4, to_eye: &Vec3x4, mask: u32) -> Vec4 {
+        let (cos, normalization) = match self.model {
+            SpecularModel::Phong => {
+                let reflection = normal.scale(normal.dot(to_light) * 2.0) - *to_light;
+                (reflection.dot(to_eye), (self.shininess + 2.0) / 2.0)
+            }
+            SpecularModel::BlinnPhong => {
+                let half = (*to_light + *to_eye).normalize_or_zero();
+                (normal.dot(&half), (self.shininess + 8.0) / 8.0)
+            }
+        };
+        let spec = powf_lanes(cos.max(Vec4::ZERO), self.shininess, mask);
+        if self.energy_conservation {
+            spec * normalization
+        } else {
+            spec
+        }
+    }
+}
+
+impl SurfaceLanes {
+    /// Lit colors of the masked lanes, the same as `Surface::shade` of each lane
+    pub fn shade(&self, eye: Vec4, lights: &[LightSource]) -> [Option<Color>; LANES] {
+        let to_eye = (Vec3x4::splat(Vec3A::from(eye)) - self.position.scale(Vec4::splat(eye.w)))
+            .normalize_or_zero();
+        let mut intensities = self.emissive;
+        let mut highlights = Vec3x4::ZERO;
+
+        for light in lights.iter() {
+            let (dir, attenuation) = match self.get_incidence(light) {
+                IncidenceLanes::Ambient => {
+                    intensities = intensities + Vec3x4::splat(light.spectrum);
+                    continue;
+                }
+                IncidenceLanes::Directed { dir, attenuation } => (dir, attenuation),
+            };
+
+            // Unlit lanes get no contribution, as the light is skipped for them
+            let attenuation = attenuation.max(Vec4::ZERO);
+            if attenuation.cmpeq(Vec4::ZERO).all() {
+                continue;
+            }
+
+            let n_dot_l = self.normal.dot(&dir);
+            let cos = n_dot_l.max(Vec4::ZERO);
+            let falloff = if light.concentration == 1.0 {
+                cos
+            } else {
+                powf_lanes(cos, Vec4::splat(light.concentration), self.mask)
+            };
+
+            let mut spectrum = light.spectrum;
+            if let Some(specular) = self.specular {
+                let spec = specular.calculate(&self.normal, &dir, &to_eye, self.mask);
+                let highlight = Vec4::select(
+                    n_dot_l.cmpgt(Vec4::ZERO),
+                    spec * n_dot_l * attenuation,
+                    Vec4::ZERO,
+                );
+                highlights =
+                    highlights + Vec3x4::splat(light.spectrum * specular.color).scale(highlight);
+                if specular.energy_conservation {
+                    spectrum *= Vec3A::ONE - specular.color.min(Vec3A::ONE);
+                }
+            }
+            intensities = intensities + Vec3x4::splat(spectrum).scale(falloff * attenuation);
+        }
+
+        let mut colors = [None; LANES];
+        for (i, color) in colors.iter_mut().enumerate() {
+            if self.mask & (1 << i) != 0 {
+                *color = Some(
+                    self.albedo[i]
+                        .apply_intensity(intensities.lane(i))
+                        .add_intensity(highlights.lane(i)),
+                );
+            }
+        }
+        colors
+    }
+
+    fn get_incidence(&self, light: &LightSource) -> IncidenceLanes {
+        match &light.kind {
+            // Unshadowed directional lights are the same for every lane
+            LightSourceKind::Linear {
+                dir, shadow_maps, ..
+            } if !self.receives_shadows || shadow_maps.is_empty() => IncidenceLanes::Directed {
+                dir: Vec3x4::splat(*dir),
+                attenuation: Vec4::ONE,
+            },
+            LightSourceKind::Ambient => IncidenceLanes::Ambient,
+            _ => {
+                let mut dirs = Vec3x4::ZERO;
+                let mut attenuations = Vec4::ZERO;
+                for i in (0..LANES).filter(|i| self.mask & (1 << i) != 0) {
+                    let surface = Surface {
+                        position: self.position.lane(i),
+                        normal: self.normal.lane(i),
+                        receives_shadows: self.receives_shadows,
+                        ..Default::default()
+                    };
+                    if let Incidence::Directed { dir, attenuation } = surface.get_incidence(light) {
+                        dirs.set_lane(i, dir);
+                        attenuations[i] = attenuation;
+                    }
+                }
+                IncidenceLanes::Directed {
+                    dir: dirs,
+                    attenuation: attenuations,
+                }
+            }
+        }
+    }
+}
+
+enum IncidenceLanes {
+    Ambient,
+    Directed { dir: Vec3x4, attenuation: Vec4 },
+}
+
+/// Shader able to output unlit surface attributes,
+/// which lets its models be drawn by the deferred shading
+pub trait SurfaceShader: Shader {
+    /// Returns `None` to discard the fragment
+    fn surface(&self, fragment: &Fragment<Self::Varyings>) -> Option<Surface>;
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3A, Vec4};
+
+    use super::{Specular, SpecularLanes, Surface, SurfaceLanes};
+    use crate::{
+        math::lanes::{Vec3x4, LANES},
+        visual::{
+            color::color::Color,
+            rendering::{
+                light_source::{Attenuation, LightSource, LightSourceKind},
+                material::SpecularModel,
+                shadow::shadow_settings::{CubeShadowSettings, ShadowSettings},
+            },
+        },
+    };
+
+    #[test]
+    fn lanes_are_lit_like_surfaces() {
+        let lights = [
+            LightSource::new(
+                LightSourceKind::Linear {
+                    dir: Vec3A::new(0.0, 2.0, 1.0).normalize(),
+                    shadow: ShadowSettings::default(),
+                    shadow_maps: Vec::new(),
+                },
+                Vec3A::ONE * 0.5,
+                2.0,
+            ),
+            LightSource::new(
+                LightSourceKind::Point {
+                    position: Vec3A::new(1.0, 1.0, 2.0),
+                    attenuation: Attenuation::InverseSquare { radius: 5.0 },
+                    shadow: CubeShadowSettings::default(),
+                    shadow_map: None,
+                },
+                Vec3A::new(0.8, 0.6, 0.4),
+                1.0,
+            ),
+            LightSource::new(LightSourceKind::Ambient, Vec3A::ONE * 0.1, 1.0),
+        ];
+        let eye = Vec4::new(0.0, 0.0, 5.0, 1.0);
+
+        for model in [SpecularModel::Phong, SpecularModel::BlinnPhong] {
+            let surfaces: [Surface; LANES] = [0.0, 1.0, 2.0, 3.0].map(|i| Surface {
+                albedo: Color::from_vec4(Vec4::new(0.2 * i, 0.5, 0.9 - 0.2 * i, 1.0)),
+                position: Vec3A::new(i - 1.5, 0.5 * i, -0.3 * i),
+                // The last lane faces away from the lights
+                normal: Vec3A::new(1.0 - i, 1.0, 2.0 - i).normalize(),
+                specular: Some(Specular {
+                    model,
+                    color: Vec3A::new(0.5, 0.5, 1.5),
+                    shininess: 4.0 + 10.0 * i,
+                    energy_conservation: true,
+                }),
+                emissive: Vec3A::new(0.1 * i, 0.0, 0.0),
+                metallic_roughness: None,
+                receives_shadows: true,
+            });
+            let lanes = SurfaceLanes {
+                albedo: surfaces.map(|s| s.albedo),
+                position: Vec3x4::from_lanes(surfaces.map(|s| s.position)),
+                normal: Vec3x4::from_lanes(surfaces.map(|s| s.normal)),
+                specular: Some(SpecularLanes {
+                    model,
+                    color: Vec3A::new(0.5, 0.5, 1.5),
+                    shininess: Vec4::from(surfaces.map(|s| s.specular.unwrap().shininess)),
+                    energy_conservation: true,
+                }),
+                emissive: Vec3x4::from_lanes(surfaces.map(|s| s.emissive)),
+                receives_shadows: true,
+                // The third lane is masked out
+                mask: 0b1011,
+            };
+
+            let colors = lanes.shade(eye, &lights);
+            for (i, surface) in surfaces.iter().enumerate() {
+                if i == 2 {
+                    assert_eq!(colors[i], None);
+                    continue;
+                }
+                let expected = surface.shade(eye, &lights);
+                let color = colors[i].unwrap();
+                let channels = |c: Color| [c.r, c.g, c.b, c.alpha];
+                let diff = channels(color)
+                    .iter()
+                    .zip(channels(expected))
+                    .map(|(a, b)| a.abs_diff(b))
+                    .max();
+                assert!(
+                    diff <= Some(1),
+                    "{model:?} lane {i}: {color:?} != {expected:?}"
+                );
+            }
+        }
+    }
+}
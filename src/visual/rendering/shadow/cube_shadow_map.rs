@@ -0,0 +1,82 @@
+use glam::{Mat4, Vec3A};
+
+use crate::{
+    math::geometry::apply_transform_matrix::vertex_apply_transform_matrix,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+};
+
+/// Directions of the cube faces from the light
+pub const CUBE_FACE_DIRS: [Vec3A; 6] = [
+    Vec3A::X,
+    Vec3A::NEG_X,
+    Vec3A::Y,
+    Vec3A::NEG_Y,
+    Vec3A::Z,
+    Vec3A::NEG_Z,
+];
+
+/// What the cube map was rendered from, it stays valid while none of it changes
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeShadowState {
+    /// Light position in the lighting space
+    pub position: Vec3A,
+    pub resolution: usize,
+    /// Lighting space transforms and alpha cutoffs of the casters
+    pub casters: Vec<(Mat4, Option<f32>)>,
+}
+
+/// Linear distances from the point light to the nearest casters in all directions
+#[derive(Clone, Debug)]
+pub struct CubeShadowMap {
+    /// Faces in the order of `CUBE_FACE_DIRS`, `f32::MAX` where nothing is drawn
+    pub faces: Vec<PlaneBuffer<f32>>,
+    /// Map the lighting space positions into the faces
+    pub transform_matrixes: Vec<Mat4>,
+    pub state: CubeShadowState,
+}
+
+impl CubeShadowMap {
+    pub fn new(state: CubeShadowState) -> CubeShadowMap {
+        let resolution = state.resolution;
+        CubeShadowMap {
+            faces: (0..CUBE_FACE_DIRS.len())
+                .map(|_| {
+                    PlaneBuffer::new(
+                        resolution,
+                        resolution,
+                        PlaneBufferCreateOption::Fill(|_| f32::MAX),
+                    )
+                })
+                .collect(),
+            transform_matrixes: vec![Mat4::IDENTITY; CUBE_FACE_DIRS.len()],
+            state,
+        }
+    }
+
+    /// Index of the face the direction from the light falls into
+    pub fn get_face(dir: Vec3A) -> usize {
+        let abs = dir.abs();
+        let (axis, value) = if abs.x >= abs.y && abs.x >= abs.z {
+            (0, dir.x)
+        } else if abs.y >= abs.z {
+            (1, dir.y)
+        } else {
+            (2, dir.z)
+        };
+        axis * 2 + usize::from(value < 0.0)
+    }
+
+    /// Face position of the lighting space position
+    pub fn project(&self, face: usize, position: Vec3A) -> Vec3A {
+        vertex_apply_transform_matrix(position, self.transform_matrixes[face])
+    }
+
+    /// Stored distance at the face position, `None` outside of the face
+    pub fn get_distance(&self, face: usize, x: f32, y: f32) -> Option<f32> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let (p, buffer) = ((x as usize, y as usize), &self.faces[face]);
+        buffer.contains(p.0, p.1).then(|| buffer[p])
+    }
+}
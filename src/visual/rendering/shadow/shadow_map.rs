@@ -0,0 +1,53 @@
+use glam::{Mat4, Vec3A};
+
+use crate::{
+    math::geometry::apply_transform_matrix::vertex_apply_transform_matrix,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+};
+
+/// Depth where no shadow caster is drawn
+pub const EMPTY_DEPTH: f32 = f32::MIN;
+
+/// Depth of the shadow casters seen from the light
+#[derive(Clone, Debug)]
+pub struct ShadowMap {
+    /// Larger depth is closer to the light, `EMPTY_DEPTH` where nothing is drawn
+    pub buffer: PlaneBuffer<f32>,
+    /// Maps the lighting space positions into the buffer
+    pub transform_matrix: Mat4,
+    /// Lighting space z of the farthest receivers the map covers,
+    /// the farther ones are covered by the next cascade
+    pub min_z: f32,
+}
+
+impl ShadowMap {
+    pub fn new(resolution: usize) -> ShadowMap {
+        ShadowMap {
+            buffer: PlaneBuffer::new(
+                resolution,
+                resolution,
+                PlaneBufferCreateOption::Fill(|_| EMPTY_DEPTH),
+            ),
+            transform_matrix: Mat4::IDENTITY,
+            min_z: f32::MIN,
+        }
+    }
+
+    pub fn get_resolution(&self) -> usize {
+        self.buffer.get_width()
+    }
+
+    /// Shadow map position of the lighting space position, the depth in z
+    pub fn project(&self, position: Vec3A) -> Vec3A {
+        vertex_apply_transform_matrix(position, self.transform_matrix)
+    }
+
+    /// Stored depth at the shadow map position, `None` outside of the map
+    pub fn get_depth(&self, x: f32, y: f32) -> Option<f32> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let p = (x as usize, y as usize);
+        self.buffer.contains(p.0, p.1).then(|| self.buffer[p])
+    }
+}
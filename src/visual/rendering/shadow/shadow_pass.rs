@@ -0,0 +1,397 @@
+use std::sync::Arc;
+
+use glam::{BVec3A, Mat4, Quat, Vec3, Vec3A};
+
+use crate::{
+    math::geometry::apply_transform_matrix::{
+        vector_apply_transform_matrix, vertex_apply_transform_matrix,
+    },
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+    visual::rendering::{
+        depth::depth_state::DepthState,
+        light_source::{LightSource, LightSourceKind},
+        matrix::{
+            camera_matrixes::CameraMatrixes,
+            projection_matrix::{
+                create_orthographic_projection_matrix, create_spot_projection_matrix,
+                get_projection_eye,
+            },
+            viewport_matrix::create_view_port_matrix,
+        },
+        wavefront_obj::{
+            wavefront_obj_depth::render_wavefront_depth,
+            wavefront_obj_processing::calculate_model_bounds,
+            wavefront_render_model::{RenderMode, WavefrontRenderModel},
+        },
+    },
+};
+
+use super::{
+    cube_shadow_map::{CubeShadowMap, CubeShadowState, CUBE_FACE_DIRS},
+    shadow_map::{ShadowMap, EMPTY_DEPTH},
+};
+
+/// Weight of the logarithmic split of the view depth between the cascades,
+/// the rest of it is split uniformly
+const CASCADE_SPLIT_LAMBDA: f32 = 0.5;
+
+/// Fraction of the fitted bounds added on every side, so the casters on the edges
+/// stay within the shadow map
+const BOUNDS_MARGIN: f32 = 0.02;
+
+/// Tangent of the half angle of the cube face frustums. It is past 1 so that the filter kernels
+/// and the casters crossing the edges between the faces stay within the faces.
+const CUBE_FACE_EXTENT: f32 = 1.1;
+
+/// Renders all shadow casting meshes into the shadow maps of the lights,
+/// so every receiver is shadowed by the whole scene. Maps are allocated with the resolution
+/// of the light's shadow settings, their viewports span the depth range.
+/// Linear lights fit orthographic projections around the bounds of the casters and receivers,
+/// their cascades split the view depth of the camera projection.
+/// Cube maps of the point lights are only rendered again once the light or the casters move.
+/// Lights are the unrotated ones of the scene, their maps are built in the rotated lighting space.
+pub fn render_shadow_maps(
+    lights: &mut [LightSource],
+    models: &[WavefrontRenderModel],
+    depth: f32,
+    projection: Mat4,
+    view_matrix: Mat4,
+    rotation_matrix: Mat4,
+) {
+    let normal_matrix = rotation_matrix.transpose().inverse();
+    let meshes = || {
+        models
+            .iter()
+            .filter(|model| matches!(model.render_mode, RenderMode::Mesh))
+    };
+    let casters: Vec<&WavefrontRenderModel> =
+        meshes().filter(|model| model.casts_shadows).collect();
+    let scene_bounds = meshes()
+        .filter(|model| model.casts_shadows || model.receives_shadows)
+        .filter_map(|model| calculate_model_bounds(model, view_matrix, rotation_matrix))
+        .reduce(|(min, max), (model_min, model_max)| (min.min(model_min), max.max(model_max)));
+
+    for light in lights.iter_mut() {
+        match &mut light.kind {
+            LightSourceKind::Linear {
+                dir,
+                shadow,
+                shadow_maps,
+            } => {
+                let scene_bounds = match scene_bounds {
+                    Some(scene_bounds) => scene_bounds,
+                    None => {
+                        shadow_maps.clear();
+                        continue;
+                    }
+                };
+                let dir = vector_apply_transform_matrix(*dir, normal_matrix).normalize();
+                let light_view = Mat4::from_quat(Quat::from_rotation_arc(dir.into(), Vec3::Z));
+                let (light_min, light_max) = transform_bounds(scene_bounds, light_view);
+
+                let ranges = calculate_cascade_ranges(scene_bounds, shadow.cascades, projection);
+                shadow_maps.resize_with(ranges.len(), || ShadowMap::new(shadow.resolution));
+
+                for (shadow_map, (min_z, max_z)) in shadow_maps.iter_mut().zip(ranges) {
+                    // Receivers of the cascade are within the slab of the scene,
+                    // while its casters are anywhere between them and the light
+                    let mut slab = scene_bounds;
+                    slab.0.z = slab.0.z.max(min_z);
+                    slab.1.z = slab.1.z.min(max_z);
+                    let (mut min, mut max) = transform_bounds(slab, light_view);
+                    min.z = light_min.z;
+                    max.z = light_max.z;
+                    let margin = (max - min) * BOUNDS_MARGIN;
+
+                    let light_projection =
+                        create_orthographic_projection_matrix(min - margin, max + margin)
+                            * light_view;
+                    render_shadow_map(
+                        shadow_map,
+                        shadow.resolution,
+                        &casters,
+                        depth,
+                        light_projection,
+                        view_matrix,
+                        rotation_matrix,
+                    );
+                    shadow_map.min_z = min_z;
+                }
+            }
+            LightSourceKind::Spot {
+                position,
+                dir,
+                cone,
+                shadow,
+                shadow_map,
+                ..
+            } => {
+                let light_projection = create_spot_projection_matrix(
+                    vertex_apply_transform_matrix(*position, rotation_matrix),
+                    vector_apply_transform_matrix(*dir, normal_matrix),
+                    cone.outer_angle,
+                );
+                render_shadow_map(
+                    shadow_map.get_or_insert_with(|| ShadowMap::new(shadow.resolution)),
+                    shadow.resolution,
+                    &casters,
+                    depth,
+                    light_projection,
+                    view_matrix,
+                    rotation_matrix,
+                );
+            }
+            LightSourceKind::Point {
+                position,
+                shadow,
+                shadow_map,
+                ..
+            } => {
+                let state = CubeShadowState {
+                    position: vertex_apply_transform_matrix(*position, rotation_matrix),
+                    resolution: shadow.resolution,
+                    casters: casters
+                        .iter()
+                        .map(|model| {
+                            (
+                                model.model_matrix * rotation_matrix * view_matrix,
+                                model.material.alpha_cutoff,
+                            )
+                        })
+                        .collect(),
+                };
+                if shadow_map.as_ref().is_none_or(|map| map.state != state) {
+                    *shadow_map = Some(Arc::new(render_cube_shadow_map(
+                        state,
+                        &casters,
+                        depth,
+                        view_matrix,
+                        rotation_matrix,
+                    )));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Six depth passes around the light, their depths are then turned into linear distances
+fn render_cube_shadow_map(
+    state: CubeShadowState,
+    casters: &[&WavefrontRenderModel],
+    depth: f32,
+    view_matrix: Mat4,
+    rotation_matrix: Mat4,
+) -> CubeShadowMap {
+    let resolution = state.resolution;
+    let position = state.position;
+    let mut cube_map = CubeShadowMap::new(state);
+    let viewport_matrix =
+        create_view_port_matrix(0.0, 0.0, resolution as f32, resolution as f32, depth);
+    // Distance of the projection eye from the screen plane
+    let distance = 1.0 / CUBE_FACE_EXTENT;
+    let to_screen = |i: usize| (i as f32 + 0.5) * 2.0 / resolution as f32 - 1.0;
+
+    let mut depth_buffer = PlaneBuffer::new(resolution, resolution, PlaneBufferCreateOption::Blank);
+    for (face, dir) in CUBE_FACE_DIRS.iter().enumerate() {
+        let light_projection =
+            create_spot_projection_matrix(position, *dir, CUBE_FACE_EXTENT.atan());
+
+        let camera = CameraMatrixes {
+            viewport_matrix,
+            projection: light_projection,
+            view_matrix,
+            rotation_matrix,
+        };
+        render_caster_depth(&mut depth_buffer, casters, &camera);
+
+        let distances = &mut cube_map.faces[face];
+        for y in 0..resolution {
+            for x in 0..resolution {
+                let stored = depth_buffer[(x, y)];
+                if stored == EMPTY_DEPTH {
+                    continue;
+                }
+                // Projected z is `distance^2 / view_depth - distance`,
+                // it is negative beyond the light for the casters behind it
+                let view_depth = distance * distance / (stored * 2.0 / depth - 1.0 + distance);
+                if view_depth <= 0.0 {
+                    continue;
+                }
+                let ray = Vec3A::new(to_screen(x) / distance, to_screen(y) / distance, 1.0);
+                distances[(x, y)] = view_depth * ray.length();
+            }
+        }
+
+        cube_map.transform_matrixes[face] = viewport_matrix * light_projection;
+    }
+
+    cube_map
+}
+
+fn render_shadow_map(
+    shadow_map: &mut ShadowMap,
+    resolution: usize,
+    casters: &[&WavefrontRenderModel],
+    depth: f32,
+    light_projection: Mat4,
+    view_matrix: Mat4,
+    rotation_matrix: Mat4,
+) {
+    if shadow_map.get_resolution() != resolution {
+        *shadow_map = ShadowMap::new(resolution);
+    }
+
+    let camera = CameraMatrixes {
+        viewport_matrix: create_view_port_matrix(
+            0.0,
+            0.0,
+            resolution as f32,
+            resolution as f32,
+            depth,
+        ),
+        projection: light_projection,
+        view_matrix,
+        rotation_matrix,
+    };
+    render_caster_depth(&mut shadow_map.buffer, casters, &camera);
+
+    shadow_map.transform_matrix = camera.viewport_matrix * light_projection;
+}
+
+/// Depth of the casters seen by the camera, `EMPTY_DEPTH` where none is drawn
+fn render_caster_depth(
+    depth_buffer: &mut PlaneBuffer<f32>,
+    casters: &[&WavefrontRenderModel],
+    camera: &CameraMatrixes,
+) {
+    // Larger depth is always closer to the light, whatever the depth state of the casters
+    let depth = DepthState::default();
+    depth_buffer.clean_with(&EMPTY_DEPTH);
+    for model in casters.iter() {
+        render_wavefront_depth(model, depth_buffer, camera, &depth);
+    }
+}
+
+/// Lighting space z ranges of the cascades, from the nearest to the viewer.
+/// The last range reaches down to `f32::MIN` so that it covers all of the farther receivers.
+fn calculate_cascade_ranges(
+    scene_bounds: (Vec3A, Vec3A),
+    cascades: usize,
+    projection: Mat4,
+) -> Vec<(f32, f32)> {
+    let cascades = cascades.max(1);
+    let (min_z, max_z) = (scene_bounds.0.z, scene_bounds.1.z);
+
+    // The viewer of the orthographic projection is infinitely far, so its depth is split uniformly
+    let eye = get_projection_eye(projection);
+    let (eye_z, lambda) = if eye.w.abs() > f32::EPSILON {
+        (eye.z / eye.w, CASCADE_SPLIT_LAMBDA)
+    } else {
+        (max_z, 0.0)
+    };
+    let far = (eye_z - min_z).max(f32::EPSILON);
+    let near = (eye_z - max_z).clamp(far * 1e-3, far);
+
+    let split_z = |i: usize| {
+        let t = i as f32 / cascades as f32;
+        let logarithmic = near * (far / near).powf(t);
+        let uniform = near + (far - near) * t;
+        eye_z - (lambda * logarithmic + (1.0 - lambda) * uniform)
+    };
+
+    (0..cascades)
+        .map(|i| {
+            let start = if i == 0 { f32::MAX } else { split_z(i) };
+            let end = if i + 1 == cascades {
+                f32::MIN
+            } else {
+                split_z(i + 1)
+            };
+            (end, start)
+        })
+        .collect()
+}
+
+/// Axis aligned bounds of the transformed box
+fn transform_bounds(bounds: (Vec3A, Vec3A), matrix: Mat4) -> (Vec3A, Vec3A) {
+    (0..8)
+        .map(|corner| {
+            let position = Vec3A::select(
+                BVec3A::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0),
+                bounds.1,
+                bounds.0,
+            );
+            vertex_apply_transform_matrix(position, matrix)
+        })
+        .fold(
+            (Vec3A::splat(f32::MAX), Vec3A::splat(f32::MIN)),
+            |(min, max), v| (min.min(v), max.max(v)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, UVec3, Vec3A};
+
+    use super::render_shadow_map;
+    use crate::{
+        visual::rendering::{
+            compare_function::CompareFunction,
+            depth::depth_state::{DepthState, PolygonOffset},
+            shadow::shadow_map::{ShadowMap, EMPTY_DEPTH},
+            texture::texture::Texture,
+            wavefront_obj::wavefront_render_model::WavefrontRenderModel,
+        },
+        wavefront::wavefront_obj::WavefrontObj,
+    };
+
+    /// Triangle over the lower left of the clip space, facing the light
+    fn triangle() -> WavefrontObj {
+        WavefrontObj {
+            vertices: vec![
+                Vec3A::new(-0.9, -0.9, 0.5),
+                Vec3A::new(0.9, -0.9, 0.5),
+                Vec3A::new(-0.9, 0.9, 0.5),
+            ],
+            vertex_colors: Vec::new(),
+            vertex_textures: vec![Vec3A::ZERO],
+            vertex_normals: vec![Vec3A::Z; 3],
+            faces: vec![vec![UVec3::new(0, 1, 2), UVec3::ZERO, UVec3::ZERO]],
+            texture: Texture::from_texels(1, 1, vec![[255; 4]]).unwrap(),
+            normal_map: None,
+            spec_map: None,
+            glow_map: None,
+            metallic_roughness_map: None,
+            occlusion_map: None,
+        }
+    }
+
+    #[test]
+    fn casters_write_regardless_of_their_depth_state() {
+        let mut model = WavefrontRenderModel::from(triangle());
+        model.depth = DepthState {
+            compare: CompareFunction::LessEqual,
+            write: false,
+            offset: PolygonOffset {
+                factor: 0.0,
+                units: 1000.0,
+            },
+        };
+
+        let mut shadow_map = ShadowMap::new(8);
+        render_shadow_map(
+            &mut shadow_map,
+            8,
+            &[&model],
+            2.0,
+            Mat4::IDENTITY,
+            Mat4::IDENTITY,
+            Mat4::IDENTITY,
+        );
+
+        // Depth 0.5 of the triangle maps into the middle of the 0..2 depth range
+        assert_eq!(shadow_map.buffer[(1, 1)], 1.5);
+        assert_eq!(shadow_map.buffer[(6, 6)], EMPTY_DEPTH);
+    }
+}
@@ -0,0 +1,306 @@
+use glam::{Vec2, Vec3A};
+
+use super::{cube_shadow_map::CubeShadowMap, shadow_map::ShadowMap};
+
+/// Poisson disk samples within the unit circle
+const POISSON_DISK: [Vec2; 16] = [
+    Vec2::new(-0.942_016_2, -0.399_062_16),
+    Vec2::new(0.945_586_1, -0.768_907_25),
+    Vec2::new(-0.094_184_1, -0.929_388_7),
+    Vec2::new(0.344_959_38, 0.293_877_6),
+    Vec2::new(-0.915_885_8, 0.457_714_32),
+    Vec2::new(-0.815_442_3, -0.879_124_64),
+    Vec2::new(-0.382_775_43, 0.276_768_45),
+    Vec2::new(0.974_844, 0.756_483_8),
+    Vec2::new(0.443_233_25, -0.975_115_54),
+    Vec2::new(0.537_429_8, -0.473_734_2),
+    Vec2::new(-0.264_969_1, -0.418_930_23),
+    Vec2::new(0.791_975_1, 0.190_901_88),
+    Vec2::new(-0.241_888_4, 0.997_065_07),
+    Vec2::new(-0.814_099_55, 0.914_375_9),
+    Vec2::new(0.199_841_26, 0.786_413_9),
+    Vec2::new(0.143_831_61, -0.141_007_9),
+];
+
+/// Upper bound of the slope-scaled bias factor, reached at grazing angles
+const MAX_SLOPE: f32 = 10.0;
+
+/// Tangent of the angle between the unit normal and the direction towards the light
+fn calculate_slope(normal: Vec3A, to_light: Vec3A) -> f32 {
+    let n_dot_l = normal.dot(to_light).clamp(0.0, 1.0);
+    ((1.0 - n_dot_l * n_dot_l).sqrt() / n_dot_l).min(MAX_SLOPE)
+}
+
+/// Sampling of the shadow map, offsets and radii are in shadow map texels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowFilter {
+    /// Single depth comparison
+    Hard,
+    /// Percentage-closer filtering over the square kernel of the odd size
+    Pcf { kernel_size: usize },
+    /// Percentage-closer filtering over the Poisson disk samples, at most 16
+    Poisson { samples: usize, radius: f32 },
+    /// Percentage-closer soft shadows: the penumbra widens with the distance between the average
+    /// blocker found within the search radius and the receiver. Light size is the penumbra width
+    /// per depth unit of that distance.
+    Pcss {
+        light_size: f32,
+        search_radius: f32,
+        samples: usize,
+    },
+}
+
+impl ShadowFilter {
+    pub fn next(&self) -> ShadowFilter {
+        match self {
+            ShadowFilter::Hard => ShadowFilter::Pcf { kernel_size: 3 },
+            ShadowFilter::Pcf { .. } => ShadowFilter::Poisson {
+                samples: 16,
+                radius: 2.5,
+            },
+            ShadowFilter::Poisson { .. } => ShadowFilter::Pcss {
+                light_size: 0.1,
+                search_radius: 8.0,
+                samples: 16,
+            },
+            ShadowFilter::Pcss { .. } => ShadowFilter::Hard,
+        }
+    }
+}
+
+/// Shadow map lookup of the light
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// Depth added to the receiver, moving it towards the light, in the shadow map depth units
+    pub constant_bias: f32,
+    /// Depth added per tangent of the angle between the normal and the light direction
+    pub slope_bias: f32,
+    /// Receiver position moved along its normal before the lookup, in the lighting space units
+    pub normal_offset: f32,
+    pub filter: ShadowFilter,
+    /// Width and height of the shadow maps in texels
+    pub resolution: usize,
+    /// Number of the shadow maps the directional light splits the view depth into,
+    /// spot lights always have a single one
+    pub cascades: usize,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        ShadowSettings {
+            constant_bias: 4.0,
+            slope_bias: 0.0,
+            normal_offset: 0.0,
+            filter: ShadowFilter::Hard,
+            resolution: 1024,
+            cascades: 1,
+        }
+    }
+}
+
+impl ShadowSettings {
+    /// Fraction of the light reaching the position, from 0 in the umbra to 1 when lit.
+    /// Position, unit normal and direction towards the light are in the lighting space.
+    pub fn calculate(
+        &self,
+        shadow_map: &ShadowMap,
+        position: Vec3A,
+        normal: Vec3A,
+        to_light: Vec3A,
+    ) -> f32 {
+        let shadow_coord = shadow_map.project(position + normal * self.normal_offset);
+
+        let slope = calculate_slope(normal, to_light);
+        let receiver_depth = shadow_coord.z + self.constant_bias + self.slope_bias * slope;
+
+        let center = Vec2::new(shadow_coord.x, shadow_coord.y);
+        // Larger depth is closer to the light, positions outside of the map are lit
+        let stored_depth = |offset: Vec2| {
+            let p = center + offset;
+            shadow_map.get_depth(p.x, p.y)
+        };
+        let lit = |offset: Vec2| match stored_depth(offset) {
+            Some(depth) if receiver_depth < depth => 0.0,
+            _ => 1.0,
+        };
+        let poisson_lit = |samples: usize, radius: f32| {
+            let samples = &POISSON_DISK[..samples.clamp(1, POISSON_DISK.len())];
+            samples
+                .iter()
+                .map(|&offset| lit(offset * radius))
+                .sum::<f32>()
+                / samples.len() as f32
+        };
+
+        match self.filter {
+            ShadowFilter::Hard => lit(Vec2::ZERO),
+            ShadowFilter::Pcf { kernel_size } => {
+                let half = (kernel_size / 2) as i32;
+                let mut sum = 0.0;
+                for y in -half..=half {
+                    for x in -half..=half {
+                        sum += lit(Vec2::new(x as f32, y as f32));
+                    }
+                }
+                sum / ((half * 2 + 1) * (half * 2 + 1)) as f32
+            }
+            ShadowFilter::Poisson { samples, radius } => poisson_lit(samples, radius),
+            ShadowFilter::Pcss {
+                light_size,
+                search_radius,
+                samples,
+            } => {
+                let samples = samples.clamp(1, POISSON_DISK.len());
+                let (blocker_sum, blocker_count) = POISSON_DISK[..samples]
+                    .iter()
+                    .filter_map(|&offset| stored_depth(offset * search_radius))
+                    .filter(|&depth| receiver_depth < depth)
+                    .fold((0.0, 0), |(sum, count), depth| (sum + depth, count + 1));
+                if blocker_count == 0 {
+                    return 1.0;
+                }
+
+                let blocker_depth = blocker_sum / blocker_count as f32;
+                let penumbra = (blocker_depth - receiver_depth) * light_size;
+                poisson_lit(samples, penumbra.max(1.0))
+            }
+        }
+    }
+}
+
+/// Cube shadow map lookup of the point light, biases and offsets are in the lighting space units.
+/// The cube map stores distances from the light rather than depths growing towards it,
+/// so unlike `ShadowSettings` the biases are subtracted to move the receiver towards the light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CubeShadowSettings {
+    /// Distance subtracted from the receiver, moving it towards the light
+    pub constant_bias: f32,
+    /// Distance subtracted per tangent of the angle between the normal and the light direction
+    pub slope_bias: f32,
+    /// Receiver position moved along its normal before the lookup
+    pub normal_offset: f32,
+    /// Percentage-closer filtering over the square kernel of the odd size, 1 for hard shadows
+    pub kernel_size: usize,
+    /// Width and height of the cube faces in texels
+    pub resolution: usize,
+}
+
+impl Default for CubeShadowSettings {
+    fn default() -> Self {
+        CubeShadowSettings {
+            constant_bias: 0.02,
+            slope_bias: 0.01,
+            normal_offset: 0.0,
+            kernel_size: 3,
+            resolution: 512,
+        }
+    }
+}
+
+impl CubeShadowSettings {
+    /// Fraction of the light reaching the position, from 0 in the umbra to 1 when lit.
+    /// Position, unit normal and direction towards the light are in the lighting space.
+    pub fn calculate(
+        &self,
+        shadow_map: &CubeShadowMap,
+        position: Vec3A,
+        normal: Vec3A,
+        to_light: Vec3A,
+    ) -> f32 {
+        let position = position + normal * self.normal_offset;
+        let from_light = position - shadow_map.state.position;
+        let face = CubeShadowMap::get_face(from_light);
+        let shadow_coord = shadow_map.project(face, position);
+
+        let slope = calculate_slope(normal, to_light);
+        let receiver_distance = from_light.length() - self.constant_bias - self.slope_bias * slope;
+
+        // Faces reach past their edges, so the kernel stays within the face of its center
+        let half = (self.kernel_size / 2) as i32;
+        let mut sum = 0.0;
+        for y in -half..=half {
+            for x in -half..=half {
+                let distance = shadow_map.get_distance(
+                    face,
+                    shadow_coord.x + x as f32,
+                    shadow_coord.y + y as f32,
+                );
+                sum += match distance {
+                    Some(distance) if receiver_distance > distance => 0.0,
+                    _ => 1.0,
+                };
+            }
+        }
+        sum / ((half * 2 + 1) * (half * 2 + 1)) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3A;
+
+    use super::{ShadowFilter, ShadowSettings};
+    use crate::visual::rendering::shadow::shadow_map::ShadowMap;
+
+    /// Map projecting positions onto themselves, with a caster over the columns left of 4
+    fn half_covered_map() -> ShadowMap {
+        let mut shadow_map = ShadowMap::new(8);
+        for y in 0..8 {
+            for x in 0..4 {
+                shadow_map.buffer[(x, y)] = 10.0;
+            }
+        }
+        shadow_map
+    }
+
+    #[test]
+    fn pcf_lit_fraction() {
+        let shadow_map = half_covered_map();
+        let settings = ShadowSettings {
+            filter: ShadowFilter::Pcf { kernel_size: 3 },
+            ..ShadowSettings::default()
+        };
+        let lit =
+            |x: f32| settings.calculate(&shadow_map, Vec3A::new(x, 4.5, 0.0), Vec3A::Z, Vec3A::Z);
+
+        // Fraction of the 3x3 kernel columns past the caster edge
+        assert_eq!(lit(1.5), 0.0);
+        assert_eq!(lit(3.5), 1.0 / 3.0);
+        assert_eq!(lit(4.5), 2.0 / 3.0);
+        assert_eq!(lit(6.5), 1.0);
+        // Samples outside of the map are lit
+        assert_eq!(lit(-5.0), 1.0);
+
+        let hard = ShadowSettings {
+            filter: ShadowFilter::Hard,
+            ..settings
+        };
+        let single = ShadowSettings {
+            filter: ShadowFilter::Pcf { kernel_size: 1 },
+            ..settings
+        };
+        for x in [1.5, 3.5, 4.5, 6.5] {
+            let position = Vec3A::new(x, 4.5, 0.0);
+            assert_eq!(
+                hard.calculate(&shadow_map, position, Vec3A::Z, Vec3A::Z),
+                single.calculate(&shadow_map, position, Vec3A::Z, Vec3A::Z)
+            );
+        }
+    }
+
+    #[test]
+    fn constant_bias_lifts_the_receiver_above_the_caster() {
+        let shadow_map = half_covered_map();
+        let position = Vec3A::new(1.5, 4.5, 7.0);
+        let lit = |constant_bias: f32| {
+            let settings = ShadowSettings {
+                constant_bias,
+                ..ShadowSettings::default()
+            };
+            settings.calculate(&shadow_map, position, Vec3A::Z, Vec3A::Z)
+        };
+
+        assert_eq!(lit(2.0), 0.0);
+        assert_eq!(lit(4.0), 1.0);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::visual::rendering::compare_function::CompareFunction;
+
+/// Update of the stored stencil value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    /// Increments up to 255
+    IncrementClamp,
+    IncrementWrap,
+    /// Decrements down to 0
+    DecrementClamp,
+    DecrementWrap,
+    Invert,
+}
+
+impl StencilOp {
+    pub fn apply(&self, stored: u8, reference: u8) -> u8 {
+        match self {
+            StencilOp::Keep => stored,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => reference,
+            StencilOp::IncrementClamp => stored.saturating_add(1),
+            StencilOp::IncrementWrap => stored.wrapping_add(1),
+            StencilOp::DecrementClamp => stored.saturating_sub(1),
+            StencilOp::DecrementWrap => stored.wrapping_sub(1),
+            StencilOp::Invert => !stored,
+        }
+    }
+}
+
+/// Stencil test and operations of a draw.
+/// The test compares `reference & read_mask` with `stored & read_mask`,
+/// operations change only the bits of `write_mask`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StencilState {
+    pub compare: CompareFunction,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    /// Applied when the stencil test fails
+    pub fail_op: StencilOp,
+    /// Applied when the stencil test passes, but the depth test fails
+    pub depth_fail_op: StencilOp,
+    /// Applied when both tests pass
+    pub pass_op: StencilOp,
+}
+
+impl StencilState {
+    /// Writes the reference into every drawn pixel, e.g. to mask the area for later draws
+    pub fn write(reference: u8) -> StencilState {
+        StencilState {
+            compare: CompareFunction::Always,
+            reference,
+            pass_op: StencilOp::Replace,
+            ..Default::default()
+        }
+    }
+
+    /// Draws only where the stored value passes the comparison with the reference
+    pub fn test(compare: CompareFunction, reference: u8) -> StencilState {
+        StencilState {
+            compare,
+            reference,
+            ..Default::default()
+        }
+    }
+
+    pub fn passes(&self, stored: u8) -> bool {
+        self.compare
+            .compare(self.reference & self.read_mask, stored & self.read_mask)
+    }
+
+    pub fn update(&self, stored: &mut u8, op: StencilOp) {
+        let value = op.apply(*stored, self.reference);
+        *stored = (*stored & !self.write_mask) | (value & self.write_mask);
+    }
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        StencilState {
+            compare: CompareFunction::Always,
+            reference: 0,
+            read_mask: u8::MAX,
+            write_mask: u8::MAX,
+            fail_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            pass_op: StencilOp::Keep,
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use glam::{Vec2, Vec4};
+
+use super::texture::{Texture, TextureLevel};
+
+const MAX_ANISOTROPY: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterMode {
+    Nearest,
+    Bilinear,
+    /// Bilinear filtering of the two closest mipmap levels, blended by LOD
+    Trilinear,
+}
+
+impl FilterMode {
+    pub fn next(&self) -> FilterMode {
+        match self {
+            FilterMode::Nearest => FilterMode::Bilinear,
+            FilterMode::Bilinear => FilterMode::Trilinear,
+            FilterMode::Trilinear => FilterMode::Nearest,
+        }
+    }
+}
+
+/// Handling of texture coordinates outside of the 0..1 range
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+impl WrapMode {
+    pub fn next(&self) -> WrapMode {
+        match self {
+            WrapMode::Repeat => WrapMode::MirroredRepeat,
+            WrapMode::MirroredRepeat => WrapMode::ClampToEdge,
+            WrapMode::ClampToEdge => WrapMode::Repeat,
+        }
+    }
+
+    fn apply(&self, coord: i64, size: usize) -> usize {
+        if (0..size as i64).contains(&coord) {
+            return coord as usize;
+        }
+        let size = size as i64;
+        (match self {
+            WrapMode::Repeat => coord.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let coord = coord.rem_euclid(size * 2);
+                if coord < size {
+                    coord
+                } else {
+                    size * 2 - 1 - coord
+                }
+            }
+            WrapMode::ClampToEdge => coord.clamp(0, size - 1),
+        }) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampler {
+    pub filter: FilterMode,
+    pub wrap: WrapMode,
+    pub use_mipmaps: bool,
+    /// Maximum number of probes along the footprint's major axis, 1 disables anisotropic filtering
+    pub max_anisotropy: u32,
+}
+
+/// Unfiltered texel fetches clamped to the texture,
+/// filtering and mipmaps are enabled per material
+impl Default for Sampler {
+    fn default() -> Self {
+        Sampler {
+            filter: FilterMode::Nearest,
+            wrap: WrapMode::ClampToEdge,
+            use_mipmaps: false,
+            max_anisotropy: 1,
+        }
+    }
+}
+
+impl Sampler {
+    /// Doubles the anisotropy up to 16x, then turns it off
+    pub fn next_anisotropy(&self) -> u32 {
+        if self.max_anisotropy >= MAX_ANISOTROPY {
+            1
+        } else {
+            self.max_anisotropy * 2
+        }
+    }
+
+    /// Samples the texture at the texture coordinates, `duv_dx` and `duv_dy` are
+    /// their screen space derivatives, which define the pixel footprint for LOD selection
+    pub fn sample(&self, texture: &Texture, uv: Vec2, duv_dx: Vec2, duv_dy: Vec2) -> Vec4 {
+        if !self.use_mipmaps {
+            return self.sample_lod(texture, uv, 0.0);
+        }
+
+        // Footprint sides in texels of the base level
+        let size = Vec2::new(texture.get_width() as f32, texture.get_height() as f32);
+        let (len_x, len_y) = ((duv_dx * size).length(), (duv_dy * size).length());
+        let (major, minor, major_axis) = if len_x >= len_y {
+            (len_x, len_y, duv_dx)
+        } else {
+            (len_y, len_x, duv_dy)
+        };
+
+        // Elongated footprint is covered by several probes along its major axis,
+        // each one filtered with the correspondingly smaller LOD
+        let probes = if self.max_anisotropy > 1 && minor > 0.0 {
+            ((major / minor).ceil() as u32).clamp(1, self.max_anisotropy.min(MAX_ANISOTROPY))
+        } else {
+            1
+        };
+        let lod = (major / probes as f32).max(f32::MIN_POSITIVE).log2();
+        if probes == 1 {
+            return self.sample_lod(texture, uv, lod);
+        }
+
+        (0..probes)
+            .map(|i| {
+                let offset = (i as f32 + 0.5) / probes as f32 - 0.5;
+                self.sample_lod(texture, uv + major_axis * offset, lod)
+            })
+            .fold(Vec4::ZERO, |sum, v| sum + v)
+            / probes as f32
+    }
+
+    fn sample_lod(&self, texture: &Texture, uv: Vec2, lod: f32) -> Vec4 {
+        let max_level = (texture.get_levels_count() - 1) as f32;
+        let lod = lod.clamp(0.0, max_level);
+
+        match self.filter {
+            FilterMode::Nearest => self.nearest(texture.get_level(lod.round() as usize), uv),
+            FilterMode::Bilinear => self.bilinear(texture.get_level(lod.round() as usize), uv),
+            FilterMode::Trilinear => {
+                let level = lod.floor();
+                let t = lod - level;
+                let lower = self.bilinear(texture.get_level(level as usize), uv);
+                if t == 0.0 {
+                    lower
+                } else {
+                    let upper = self.bilinear(texture.get_level(level as usize + 1), uv);
+                    lower.lerp(upper, t)
+                }
+            }
+        }
+    }
+
+    fn nearest(&self, level: &TextureLevel, uv: Vec2) -> Vec4 {
+        let (width, height) = (level.get_width(), level.get_height());
+        level.fetch_texel(
+            self.wrap.apply((uv.x * width as f32).floor() as i64, width),
+            self.wrap
+                .apply((uv.y * height as f32).floor() as i64, height),
+        )
+    }
+
+    fn bilinear(&self, level: &TextureLevel, uv: Vec2) -> Vec4 {
+        let (width, height) = (level.get_width(), level.get_height());
+        // Texel centers are at half-integer coordinates
+        let coords = uv * Vec2::new(width as f32, height as f32) - 0.5;
+        let base = coords.floor();
+        let t = coords - base;
+
+        let (x0, y0) = (base.x as i64, base.y as i64);
+        let (x0, x1) = (self.wrap.apply(x0, width), self.wrap.apply(x0 + 1, width));
+        let (y0, y1) = (self.wrap.apply(y0, height), self.wrap.apply(y0 + 1, height));
+
+        let top = level
+            .fetch_texel(x0, y0)
+            .lerp(level.fetch_texel(x1, y0), t.x);
+        let bottom = level
+            .fetch_texel(x0, y1)
+            .lerp(level.fetch_texel(x1, y1), t.x);
+        top.lerp(bottom, t.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec4};
+
+    use super::{FilterMode, Sampler, WrapMode};
+    use crate::visual::rendering::texture::texture::Texture;
+
+    /// Black and white texels alternating along x, so every level past the base is gray
+    fn stripes(width: usize, height: usize) -> Texture {
+        let texels = (0..width * height)
+            .map(|i| if i % 2 == 0 { [0; 4] } else { [255; 4] })
+            .collect();
+        Texture::from_texels(width, height, texels).unwrap()
+    }
+
+    fn mipmapped(filter: FilterMode) -> Sampler {
+        Sampler {
+            filter,
+            use_mipmaps: true,
+            ..Sampler::default()
+        }
+    }
+
+    #[test]
+    fn lod_follows_pixel_footprint() {
+        let texture = stripes(8, 8);
+        let uv = Vec2::new(1.5 / 8.0, 0.5 / 8.0);
+        let sampler = mipmapped(FilterMode::Nearest);
+
+        // One texel per pixel samples the base level
+        let texel = Vec2::splat(1.0 / 8.0);
+        let base = sampler.sample(&texture, uv, texel * Vec2::X, texel * Vec2::Y);
+        assert_eq!(base, Vec4::ONE);
+
+        // Two texels per pixel sample the averaged first level
+        let level = sampler.sample(&texture, uv, texel * Vec2::X * 2.0, texel * Vec2::Y * 2.0);
+        assert!(level.abs_diff_eq(Vec4::splat(128.0 / 255.0), 1e-6));
+
+        // Without mipmaps the footprint is ignored
+        let unfiltered = Sampler::default().sample(&texture, uv, texel * 8.0, texel * 8.0);
+        assert_eq!(unfiltered, Vec4::ONE);
+    }
+
+    #[test]
+    fn trilinear_blends_between_levels() {
+        let texture = stripes(8, 8);
+        let uv = Vec2::new(1.5 / 8.0, 1.5 / 8.0);
+        let sampler = mipmapped(FilterMode::Trilinear);
+
+        // LOD 0.5 is halfway between the white base texel and the gray first level
+        let footprint = Vec2::splat(2f32.sqrt() / 8.0);
+        let value = sampler.sample(&texture, uv, footprint * Vec2::X, footprint * Vec2::Y);
+        let expected = (1.0 + 128.0 / 255.0) / 2.0;
+        assert!(value.abs_diff_eq(Vec4::splat(expected), 1e-3), "{value}");
+    }
+
+    #[test]
+    fn default_sampler_clamps_to_edge() {
+        let texture = stripes(4, 1);
+        let sampler = Sampler::default();
+        assert_eq!(sampler.wrap, WrapMode::ClampToEdge);
+
+        let past_end = sampler.sample(&texture, Vec2::new(1.5, 0.5), Vec2::ZERO, Vec2::ZERO);
+        let before_start = sampler.sample(&texture, Vec2::new(-0.5, 0.5), Vec2::ZERO, Vec2::ZERO);
+        assert_eq!(past_end, Vec4::ONE);
+        assert_eq!(before_start, Vec4::ZERO);
+    }
+
+    #[test]
+    fn odd_levels_keep_last_column_and_row() {
+        // White last column and row of the 3x3 texture
+        let texels = (0..9)
+            .map(|i| if i % 3 == 2 || i / 3 == 2 { [255; 4] } else { [0; 4] })
+            .collect();
+        let texture = Texture::from_texels(3, 3, texels).unwrap();
+
+        let level = texture.get_level(1);
+        assert_eq!((level.get_width(), level.get_height()), (2, 2));
+        assert_eq!(level.fetch_texel(0, 0), Vec4::ZERO);
+        assert_eq!(level.fetch_texel(1, 0), Vec4::ONE);
+        assert_eq!(level.fetch_texel(0, 1), Vec4::ONE);
+        assert_eq!(level.fetch_texel(1, 1), Vec4::ONE);
+        assert_eq!(texture.get_levels_count(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+use glam::Vec4;
+use image::DynamicImage;
+
+/// Single level of the mipmap chain, rows go along the v texture coordinate
+#[derive(Clone, Debug)]
+pub struct TextureLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<[u8; 4]>,
+}
+
+impl TextureLevel {
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Texel channels in 0..1 range
+    pub fn fetch_texel(&self, x: usize, y: usize) -> Vec4 {
+        let [r, g, b, a] = self.texels[x + y * self.width];
+        Vec4::new(r as f32, g as f32, b as f32, a as f32) * (1.0 / 255.0)
+    }
+
+    // Each texel is the average of the 2x2 block of the previous level, the block is clamped
+    // to the level, so the last column/row of odd sized levels is averaged with itself
+    fn downsample(&self) -> TextureLevel {
+        let width = self.width.div_ceil(2);
+        let height = self.height.div_ceil(2);
+
+        let mut texels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, y0) = (x * 2, y * 2);
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                let block = [
+                    self.texels[x0 + y0 * self.width],
+                    self.texels[x1 + y0 * self.width],
+                    self.texels[x0 + y1 * self.width],
+                    self.texels[x1 + y1 * self.width],
+                ];
+                texels.push([0, 1, 2, 3].map(|channel| {
+                    let sum: u32 = block.iter().map(|texel| texel[channel] as u32).sum();
+                    ((sum + 2) / 4) as u8
+                }));
+            }
+        }
+
+        TextureLevel {
+            width,
+            height,
+            texels,
+        }
+    }
+}
+
+/// Texture with precomputed mipmap chain, down to the 1x1 level.
+/// Texels are stored as 8-bit RGBA.
+#[derive(Clone, Debug)]
+pub struct Texture {
+    levels: Vec<TextureLevel>,
+}
+
+impl Texture {
+    pub fn from_texels(
+        width: usize,
+        height: usize,
+        texels: Vec<[u8; 4]>,
+    ) -> Result<Texture, String> {
+        if width == 0 || height == 0 || texels.len() != width * height {
+            return Err(format!(
+                "Can't create {}x{} texture from {} texels",
+                width,
+                height,
+                texels.len()
+            ));
+        }
+
+        let mut levels = vec![TextureLevel {
+            width,
+            height,
+            texels,
+        }];
+        loop {
+            let last = levels.last().unwrap();
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+
+        Ok(Texture { levels })
+    }
+
+    pub fn from_image(image: &DynamicImage) -> Result<Texture, String> {
+        let rgba = image.to_rgba8();
+        let texels = rgba.pixels().map(|pixel| pixel.0).collect();
+        Texture::from_texels(rgba.width() as usize, rgba.height() as usize, texels)
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    pub fn get_levels_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn get_level(&self, level: usize) -> &TextureLevel {
+        &self.levels[level]
+    }
+}
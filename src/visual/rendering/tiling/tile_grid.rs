@@ -0,0 +1,81 @@
+use crate::{math::geometry::rect::Rect, visual::vertex::ScreenTriangle};
+
+pub struct TileGrid {
+    screen_rect: Rect,
+    tile_size: usize,
+    columns: usize,
+    rows: usize,
+    tiles: Vec<Rect>,
+}
+
+impl TileGrid {
+    pub fn new(screen_rect: Rect, tile_size: usize) -> TileGrid {
+        let columns = screen_rect.size.width.div_ceil(tile_size);
+        let rows = screen_rect.size.height.div_ceil(tile_size);
+
+        let mut tiles = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let (x, y) = (column * tile_size, row * tile_size);
+                tiles.push(Rect::new(
+                    screen_rect.x + x,
+                    screen_rect.y + y,
+                    tile_size.min(screen_rect.size.width - x),
+                    tile_size.min(screen_rect.size.height - y),
+                ));
+            }
+        }
+
+        TileGrid {
+            screen_rect,
+            tile_size,
+            columns,
+            rows,
+            tiles,
+        }
+    }
+
+    pub fn get_tiles(&self) -> &Vec<Rect> {
+        &self.tiles
+    }
+
+    /// Returns indices of faces overlapping each tile, preserving the faces order
+    /// so that every tile sees the same sequence of writes as a single-threaded pass.
+    /// Faces bounds are extended by `margin` to account for off-center samples.
+    pub fn bin_faces<T: ScreenTriangle>(&self, faces: &[T], margin: f32) -> Vec<Vec<usize>> {
+        let mut bins = vec![Vec::new(); self.tiles.len()];
+        if self.tiles.is_empty() {
+            return bins;
+        }
+
+        for (face_idx, face) in faces.iter().enumerate() {
+            let [p0, p1, p2] = face.get_screen_positions();
+            let (min, max) = (p0.min(p1).min(p2), p0.max(p1).max(p2));
+
+            let (first_column, last_column) = (
+                self.column_of(min.x - margin),
+                self.column_of(max.x + margin).min(self.columns - 1),
+            );
+            let (first_row, last_row) = (
+                self.row_of(min.y - margin),
+                self.row_of(max.y + margin).min(self.rows - 1),
+            );
+
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    bins[row * self.columns + column].push(face_idx);
+                }
+            }
+        }
+
+        bins
+    }
+
+    fn column_of(&self, x: f32) -> usize {
+        (x.max(0.0) as usize).saturating_sub(self.screen_rect.x) / self.tile_size
+    }
+
+    fn row_of(&self, y: f32) -> usize {
+        (y.max(0.0) as usize).saturating_sub(self.screen_rect.y) / self.tile_size
+    }
+}
@@ -0,0 +1,16 @@
+use crate::math::geometry::rect::Rect;
+
+/// Render target which can be split into tiles rendered in parallel
+pub trait TileTarget: Sized + Send + Sync {
+    /// Area of the screen covered by the target.
+    /// Differs from `(0, 0, width, height)` only for tiles.
+    fn get_screen_rect(&self) -> Rect;
+
+    /// Distance of the samples from the pixel they belong to,
+    /// faces are binned into the tiles with that margin
+    fn get_sample_radius(&self) -> f32;
+
+    fn copy_tile(&self, tile: &Rect) -> Self;
+
+    fn paste_tile(&mut self, tile: &Self);
+}
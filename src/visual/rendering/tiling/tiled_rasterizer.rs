@@ -0,0 +1,229 @@
+use rayon::{
+    prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    ThreadPool, ThreadPoolBuilder,
+};
+
+use crate::visual::vertex::ScreenTriangle;
+
+use super::{tile_grid::TileGrid, tile_target::TileTarget};
+
+pub struct TiledRasterizer {
+    thread_pool: Option<ThreadPool>,
+    tile_size: usize,
+    use_simd: bool,
+    occlusion_culling: bool,
+    depth_pre_pass: bool,
+}
+
+impl TiledRasterizer {
+    /// `threads == 0` uses all available cores, `threads == 1` renders
+    /// straight into the canvas without tiling.
+    pub fn new(threads: usize, tile_size: usize, use_simd: bool) -> TiledRasterizer {
+        let thread_pool = if threads == 1 {
+            None
+        } else {
+            Some(
+                ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .expect("Unable to create rasterization thread pool"),
+            )
+        };
+
+        TiledRasterizer {
+            thread_pool,
+            tile_size,
+            use_simd,
+            occlusion_culling: false,
+            depth_pre_pass: false,
+        }
+    }
+
+    pub fn uses_simd(&self) -> bool {
+        self.use_simd
+    }
+
+    pub fn set_use_simd(&mut self, use_simd: bool) {
+        self.use_simd = use_simd;
+    }
+
+    /// Models and faces hidden behind the already drawn ones are skipped
+    /// by the hierarchical z-buffer test
+    pub fn uses_occlusion_culling(&self) -> bool {
+        self.occlusion_culling
+    }
+
+    pub fn set_occlusion_culling(&mut self, occlusion_culling: bool) {
+        self.occlusion_culling = occlusion_culling;
+    }
+
+    /// Opaque models are drawn into the depth first,
+    /// so that shading runs only for the visible fragments
+    pub fn uses_depth_pre_pass(&self) -> bool {
+        self.depth_pre_pass
+    }
+
+    pub fn set_depth_pre_pass(&mut self, depth_pre_pass: bool) {
+        self.depth_pre_pass = depth_pre_pass;
+    }
+
+    pub fn get_threads_count(&self) -> usize {
+        self.thread_pool
+            .as_ref()
+            .map(ThreadPool::current_num_threads)
+            .unwrap_or(1)
+    }
+
+    pub fn rasterize<C, T, F>(&self, canvas: &mut C, faces: &[T], render_face: F)
+    where
+        C: TileTarget,
+        T: ScreenTriangle + Sync,
+        F: Fn(&T, &mut C) + Sync,
+    {
+        let thread_pool = match &self.thread_pool {
+            Some(thread_pool) => thread_pool,
+            None => {
+                for face in faces.iter() {
+                    render_face(face, canvas);
+                }
+                return;
+            }
+        };
+
+        let tile_grid = TileGrid::new(canvas.get_screen_rect(), self.tile_size);
+        let bins = tile_grid.bin_faces(faces, canvas.get_sample_radius());
+
+        let source: &C = canvas;
+        let rendered_tiles: Vec<C> = thread_pool.install(|| {
+            tile_grid
+                .get_tiles()
+                .par_iter()
+                .zip(bins.par_iter())
+                .filter(|(_, bin)| !bin.is_empty())
+                .map(|(tile, bin)| {
+                    let mut tile_canvas = source.copy_tile(tile);
+                    for &face_idx in bin.iter() {
+                        render_face(&faces[face_idx], &mut tile_canvas);
+                    }
+                    tile_canvas
+                })
+                .collect()
+        });
+
+        for tile_canvas in rendered_tiles.iter() {
+            canvas.paste_tile(tile_canvas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3A, Vec4};
+
+    use super::TiledRasterizer;
+    use crate::{
+        plane_buffer::plane_buffer::PlaneBufferCreateOption,
+        visual::{
+            color::color::Color,
+            drawing_buffer::DrawingBuffer,
+            rendering::{
+                render_state::RenderState,
+                shader::shader::{
+                    Fragment, ShadedFace, ShadedVertex, Shader, VertexInput, VertexOutput,
+                },
+                triangle::{
+                    triangle_rasterization::render_triangle_mesh,
+                    triangle_rasterization_simd::render_triangle_mesh_simd,
+                },
+            },
+        },
+    };
+
+    /// Interpolated varyings as the fragment color
+    struct GradientShader;
+
+    impl Shader for GradientShader {
+        type Varyings = Vec3A;
+
+        fn vertex(&self, input: &VertexInput) -> VertexOutput<Vec3A> {
+            VertexOutput {
+                clip_pos: Vec4::from((input.position, 1.0)),
+                varyings: input.normal,
+            }
+        }
+
+        fn fragment(&self, fragment: &Fragment<Vec3A>) -> Option<Color> {
+            Some(Color::from_vec4(Vec4::from((fragment.varyings, 1.0))))
+        }
+    }
+
+    /// Grid of the quads with the shared edges crossing the tiles,
+    /// overlapped by the triangles at varying depth
+    fn mesh() -> Vec<ShadedFace<Vec3A>> {
+        let vertex = |x: f32, y: f32, z: f32| ShadedVertex {
+            screen_pos: Vec3A::new(x, y, z),
+            varyings: Vec3A::new(x / 64.0, y / 64.0, z / 40.0),
+        };
+        let mut faces = Vec::new();
+        for row in 0..7 {
+            for column in 0..7 {
+                let (x, y) = (column as f32 * 9.3 + 0.7, row as f32 * 9.1 + 0.4);
+                let z = 10.0 + ((row * 7 + column) % 5) as f32;
+                let corners = [
+                    vertex(x, y, z),
+                    vertex(x + 9.3, y, z + 1.0),
+                    vertex(x + 9.3, y + 9.1, z),
+                    vertex(x, y + 9.1, z - 1.0),
+                ];
+                for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+                    faces.push(ShadedFace {
+                        vertices: [corners[a], corners[b], corners[c]],
+                        is_front_facing: true,
+                    });
+                }
+            }
+        }
+        for i in 0..6 {
+            let offset = i as f32 * 7.7;
+            faces.push(ShadedFace {
+                vertices: [
+                    vertex(offset + 2.1, 5.3, 30.0 - i as f32),
+                    vertex(offset + 21.6, 31.4, 5.0 + i as f32),
+                    vertex(offset - 3.2, 58.9, 20.0),
+                ],
+                is_front_facing: true,
+            });
+        }
+        faces
+    }
+
+    #[test]
+    fn threads_render_the_same_buffer() {
+        let faces = mesh();
+        let render = |threads: usize, simd: bool| {
+            let rasterizer = TiledRasterizer::new(threads, 16, simd);
+            let mut canvas = DrawingBuffer::new(64, 64, PlaneBufferCreateOption::Blank);
+            let state = RenderState::default();
+            rasterizer.rasterize(&mut canvas, &faces, |face, canvas| {
+                if simd {
+                    render_triangle_mesh_simd(face, canvas, &GradientShader, &state);
+                } else {
+                    render_triangle_mesh(face, canvas, &GradientShader, &state);
+                }
+            });
+            canvas
+        };
+
+        for simd in [false, true] {
+            let (single, tiled) = (render(1, simd), render(4, simd));
+            assert!(single.get_buffer().iter().any(|color| color.alpha != 0));
+            assert_eq!(single.get_buffer(), tiled.get_buffer(), "simd: {}", simd);
+            assert_eq!(
+                single.get_z_buffer().get_buffer(),
+                tiled.get_z_buffer().get_buffer(),
+                "simd: {}",
+                simd
+            );
+        }
+    }
+}
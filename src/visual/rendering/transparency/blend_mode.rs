@@ -0,0 +1,48 @@
+use glam::{Vec3A, Vec4};
+
+use crate::visual::color::color::Color;
+
+/// Combination of the fragment color with the color already in the canvas,
+/// weighted by the fragment alpha
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Fragment replaces the canvas color, alpha is ignored
+    Opaque,
+    Alpha,
+    Additive,
+    Multiply,
+}
+
+impl BlendMode {
+    pub fn next(&self) -> BlendMode {
+        match self {
+            BlendMode::Opaque => BlendMode::Alpha,
+            BlendMode::Alpha => BlendMode::Additive,
+            BlendMode::Additive => BlendMode::Multiply,
+            BlendMode::Multiply => BlendMode::Opaque,
+        }
+    }
+
+    pub fn blend(&self, source: Color, destination: Color) -> Color {
+        if *self == BlendMode::Opaque {
+            return source;
+        }
+
+        let (source, destination) = (source.to_vec4(), destination.to_vec4());
+        let alpha = source.w;
+        let (source_rgb, destination_rgb) = (Vec3A::from(source), Vec3A::from(destination));
+
+        let rgb = match self {
+            BlendMode::Opaque => source_rgb,
+            BlendMode::Alpha => destination_rgb.lerp(source_rgb, alpha),
+            BlendMode::Additive => destination_rgb + source_rgb * alpha,
+            BlendMode::Multiply => destination_rgb * (Vec3A::ONE - alpha + source_rgb * alpha),
+        };
+        let destination_alpha = match self {
+            BlendMode::Alpha => alpha + destination.w * (1.0 - alpha),
+            _ => destination.w,
+        };
+
+        Color::from_vec4(Vec4::from((rgb, destination_alpha)))
+    }
+}
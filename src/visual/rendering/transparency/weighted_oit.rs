@@ -0,0 +1,78 @@
+use glam::{Vec3A, Vec4};
+
+use crate::{
+    math::geometry::rect::Rect,
+    plane_buffer::plane_buffer::{PlaneBuffer, PlaneBufferCreateOption},
+    visual::color::color::Color,
+};
+
+/// Accumulation planes of weighted blended order-independent transparency
+/// (McGuire and Bavoil, 2013). Alpha blended fragments are summed up
+/// with depth based weights, so their drawing order doesn't matter.
+#[derive(Clone, Debug)]
+pub struct OitBuffers {
+    // Weighted sum of premultiplied colors and their alphas
+    accumulation: PlaneBuffer<Vec4>,
+    // Product of `1 - alpha`, i.e. how much of the background stays visible
+    revealage: PlaneBuffer<f32>,
+    depth_range: f32,
+}
+
+impl OitBuffers {
+    /// `depth_range` is the depth of the nearest possible fragment, the farthest one is at 0
+    pub fn new(width: usize, height: usize, depth_range: f32) -> OitBuffers {
+        OitBuffers {
+            accumulation: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Blank),
+            revealage: PlaneBuffer::new(width, height, PlaneBufferCreateOption::Fill(|_| 1.0)),
+            depth_range,
+        }
+    }
+
+    pub fn accumulate(&mut self, p: (usize, usize), color: Color, z_depth: f32) {
+        let color = color.to_vec4();
+        let alpha = color.w;
+        if alpha <= 0.0 {
+            return;
+        }
+
+        // Nearer and more opaque fragments dominate the average
+        let distance = (1.0 - z_depth / self.depth_range).clamp(0.0, 1.0);
+        let weight = alpha
+            * ((alpha * 8.0 + 0.01).powi(3) * 1e8 * (1.0 - distance * 0.9).powi(3))
+                .clamp(1e-2, 3e3);
+
+        self.accumulation[p] += Vec4::from((Vec3A::from(color) * alpha, alpha)) * weight;
+        self.revealage[p] *= 1.0 - alpha;
+    }
+
+    /// Blends the averaged transparent color over the color plane
+    pub fn composite(&self, color_buffer: &mut PlaneBuffer<Color>) {
+        for ((color, accumulation), revealage) in color_buffer
+            .get_buffer_mut()
+            .iter_mut()
+            .zip(self.accumulation.get_buffer().iter())
+            .zip(self.revealage.get_buffer().iter())
+        {
+            if *revealage >= 1.0 {
+                continue;
+            }
+            let average = Vec3A::from(*accumulation) / accumulation.w.max(1e-5);
+            let background = color.to_vec4();
+            let rgb = average.lerp(Vec3A::from(background), *revealage);
+            *color = Color::from_vec4(Vec4::from((rgb, 1.0 - (1.0 - background.w) * revealage)));
+        }
+    }
+
+    pub fn copy_region(&self, region: &Rect) -> OitBuffers {
+        OitBuffers {
+            accumulation: self.accumulation.copy_region(region),
+            revealage: self.revealage.copy_region(region),
+            depth_range: self.depth_range,
+        }
+    }
+
+    pub fn paste_region(&mut self, region: &Rect, source: &OitBuffers) {
+        self.accumulation.paste_region(region, &source.accumulation);
+        self.revealage.paste_region(region, &source.revealage);
+    }
+}
@@ -1,4 +1,4 @@
-use glam::{Vec2, Vec3A};
+use glam::Vec3A;
 
 use crate::{
     derive_div_by, derive_mul_by, derive_self_add, derive_self_sub, visual::vertex::Vertex,
@@ -8,23 +8,36 @@ use crate::{
 pub struct InterpolationValues {
     pub y: f32,
     pub z_depth: f32,
-    pub uv: Vec2,
-    pub normal: Vec3A,
+    // Weights of the triangle vertices, varyings are interpolated by them
+    pub barycentric: Vec3A,
 }
 
-impl From<Vertex> for InterpolationValues {
-    fn from(v: Vertex) -> Self {
+impl InterpolationValues {
+    pub fn new(screen_pos: Vec3A, barycentric: Vec3A) -> InterpolationValues {
         InterpolationValues {
-            y: v.y,
-            z_depth: v.z,
-            uv: v.uv,
-            normal: v.normal,
+            y: screen_pos.y,
+            z_depth: screen_pos.z,
+            barycentric,
+        }
+    }
+
+    pub fn lerp(self, other: InterpolationValues, t: f32) -> InterpolationValues {
+        InterpolationValues {
+            y: self.y + (other.y - self.y) * t,
+            z_depth: self.z_depth + (other.z_depth - self.z_depth) * t,
+            barycentric: self.barycentric.lerp(other.barycentric, t),
         }
     }
 }
 
-derive_self_add!(InterpolationValues, y, z_depth, uv, normal);
-derive_self_sub!(InterpolationValues, y, z_depth, uv, normal);
+impl From<Vertex> for InterpolationValues {
+    fn from(v: Vertex) -> Self {
+        InterpolationValues::new(v.screen_pos, Vec3A::ZERO)
+    }
+}
+
+derive_self_add!(InterpolationValues, y, z_depth, barycentric);
+derive_self_sub!(InterpolationValues, y, z_depth, barycentric);
 derive_mul_by!(
     InterpolationValues,
     i32,
@@ -32,9 +45,7 @@ derive_mul_by!(
     f32,
     z_depth,
     f32,
-    uv,
-    f32,
-    normal,
+    barycentric,
     f32
 );
 derive_div_by!(
@@ -44,8 +55,6 @@ derive_div_by!(
     f32,
     z_depth,
     f32,
-    uv,
-    f32,
-    normal,
+    barycentric,
     f32
 );
@@ -1,77 +1,81 @@
+use glam::Vec2;
+
 use crate::{
-    math::interpolation::Interpolator,
+    math::geometry::rect::Rect,
     plane_buffer::plane_buffer::PlaneBuffer,
-    visual::{rendering::triangle::interpolation_values::InterpolationValues, vertex::Vertex},
+    visual::{
+        rendering::{
+            depth::depth_state::DepthState,
+            shader::shader::{ShadedFace, ShadedVertex},
+            texture::{sampler::Sampler, texture::Texture},
+            triangle::triangle_scanline::scan_triangle,
+        },
+        vertex::Vertex,
+    },
 };
 
-pub fn render_triangle_depth(vertices: &[Vertex; 3], depth_buffer: &mut PlaneBuffer<f32>) {
-    let mut vertices_sorted_by_x = vertices.clone();
-    vertices_sorted_by_x.sort_unstable_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
-
-    let (l_p, m_p, r_p) = unsafe {
-        (
-            *vertices_sorted_by_x.get_unchecked(0),
-            *vertices_sorted_by_x.get_unchecked(1),
-            *vertices_sorted_by_x.get_unchecked(2),
-        )
-    };
-
-    let (l_v, m_v, r_v) = (
-        InterpolationValues::from(l_p),
-        InterpolationValues::from(m_p),
-        InterpolationValues::from(r_p),
-    );
-
-    let (l_calc, long_calc, r_calc) = (
-        Interpolator::from((l_p.x as i32, m_p.x as i32)),
-        Interpolator::from((l_p.x as i32, r_p.x as i32)),
-        Interpolator::from((m_p.x as i32, r_p.x as i32)),
-    );
-
-    let d_long_v = r_v - l_v;
-
-    let mut filler = |short_calc: Interpolator<i32>,
-                      v_start: InterpolationValues,
-                      v_end: InterpolationValues| {
-        let d_interp = v_end - v_start;
-        let range = short_calc.get_interpolation_range();
-
-        for x in range {
-            let mut v1 = short_calc.interpolate(x, d_interp, v_start);
-            let mut v2 = long_calc.interpolate(x, d_long_v, l_v);
-
-            if v1.y > v2.y {
-                (v1, v2) = (v2, v1);
-            }
-
-            let (y1, y2) = (v1.y as i32, v2.y as i32);
-
-            let local_calc = Interpolator::new(y1, y2);
-            let local_d_v = v2 - v1;
-
-            for y in y1..y2 {
-                let p = (x as usize, y as usize);
-                if !depth_buffer.contains(p.0, p.1) {
-                    continue;
-                }
-
-                let local_v = local_calc.interpolate(y, local_d_v, v1);
+/// Alpha test of the depth only rendering, so that cutout faces
+/// leave the same holes in the depth as in the shaded image
+pub struct AlphaCutout<'a> {
+    /// Alpha of the fragments is the opacity alone without it, as for `NormalShader`
+    pub texture: Option<&'a Texture>,
+    pub sampler: Sampler,
+    pub opacity: f32,
+    pub cutoff: f32,
+}
 
-                let InterpolationValues {
-                    z_depth,
-                    ..
-                } = local_v;
+impl AlphaCutout<'_> {
+    /// Same alpha as the fragments of the shaders, sampled with the same LOD
+    fn passes(&self, uv: Vec2, duv_dx: Vec2, duv_dy: Vec2) -> bool {
+        let alpha = self.texture.map_or(1.0, |texture| {
+            self.sampler.sample(texture, uv, duv_dx, duv_dy).w
+        });
+        alpha * self.opacity >= self.cutoff
+    }
+}
 
-                let z_val = &mut depth_buffer[p];
-                if *z_val > z_depth {
-                    continue;
-                }
+/// Depth only rasterization with the depth test, write and polygon offset of the state.
+/// Pixel centers are sampled the same way as by the shaded rasterization.
+pub fn render_triangle_depth(
+    vertices: &[Vertex; 3],
+    depth_buffer: &mut PlaneBuffer<f32>,
+    depth: &DepthState,
+    alpha_cutout: Option<&AlphaCutout>,
+) {
+    let face = ShadedFace {
+        vertices: vertices.map(|vertex| ShadedVertex {
+            screen_pos: vertex.screen_pos,
+            varyings: vertex.uv,
+        }),
+        is_front_facing: true,
+    };
+    let mut positions = vertices.map(|vertex| vertex.screen_pos);
+    if !depth.offset.is_none() {
+        let offset = depth.offset.calculate(positions);
+        for position in positions.iter_mut() {
+            position.z -= offset;
+        }
+    }
+    let (duv_dx, duv_dy) = face.derivatives(|uv| *uv);
+    let screen_rect = Rect::new(0, 0, depth_buffer.get_width(), depth_buffer.get_height());
+
+    scan_triangle(positions, screen_rect, |p, screen_pos, barycentric| {
+        let z_val = &mut depth_buffer[p];
+        if !depth.compare.compare(screen_pos.z, *z_val) {
+            return;
+        }
 
-                *z_val = z_depth;
+        if let Some(alpha_cutout) = alpha_cutout {
+            let uv = vertices[0].uv * barycentric.x
+                + vertices[1].uv * barycentric.y
+                + vertices[2].uv * barycentric.z;
+            if !alpha_cutout.passes(uv, duv_dx, duv_dy) {
+                return;
             }
         }
-    };
 
-    filler(l_calc, l_v, m_v);
-    filler(r_calc, m_v, r_v);
+        if depth.write {
+            *z_val = screen_pos.z;
+        }
+    });
 }
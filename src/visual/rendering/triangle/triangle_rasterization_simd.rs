@@ -0,0 +1,515 @@
+use glam::{BVec4A, Vec3A, Vec4};
+
+use crate::{
+    math::lanes::LANES,
+    visual::{
+        color::color::Color,
+        drawing_buffer::DrawingBuffer,
+        multisampling::MAX_SAMPLES,
+        rendering::{
+            render_state::RenderState,
+            shader::shader::{FragmentLanes, ShadedFace, Shader},
+            transparency::blend_mode::BlendMode,
+        },
+        vertex::ScreenTriangle,
+    },
+};
+
+// Pixel centers of the lanes relative to the first pixel of the span
+const LANE_OFFSETS: Vec4 = Vec4::new(0.5, 1.5, 2.5, 3.5);
+
+/// Edge function `a * x + b * y + c` prescaled by the inverse triangle area,
+/// so that it yields barycentric weight of the opposite vertex
+struct EdgeFunction {
+    a: f32,
+    b: f32,
+    c: f32,
+}
+
+impl EdgeFunction {
+    fn new(from: Vec3A, to: Vec3A, inv_area: f32) -> EdgeFunction {
+        EdgeFunction {
+            a: (from.y - to.y) * inv_area,
+            b: (to.x - from.x) * inv_area,
+            c: (from.x * to.y - from.y * to.x) * inv_area,
+        }
+    }
+
+    fn evaluate(&self, xs: Vec4, y: f32) -> Vec4 {
+        xs * self.a + Vec4::splat(self.b * y + self.c)
+    }
+
+    /// Narrows `x_range` of row `y` to pixels having a point within `radius`
+    /// (along either axis) where the function is non-negative
+    fn clip_row(&self, y: f32, radius: f32, x_range: &mut (f32, f32)) {
+        let row_value = self.b * y + self.c + (self.a.abs() + self.b.abs()) * radius;
+        if self.a > 0.0 {
+            x_range.0 = x_range.0.max(-row_value / self.a);
+        } else if self.a < 0.0 {
+            x_range.1 = x_range.1.min(-row_value / self.a);
+        } else if row_value < 0.0 {
+            *x_range = (1.0, 0.0);
+        }
+    }
+}
+
+fn lanes(mask: u32) -> impl Iterator<Item = usize> {
+    (0..LANES).filter(move |i| mask & (1 << i) != 0)
+}
+
+/// Edge function rasterization in spans of 4 pixels using SSE-backed `Vec4` lanes.
+/// Coverage, depth test and barycentric weights are computed for the whole span,
+/// which is then shaded at once by `Shader::fragment_lanes`.
+/// Also handles multisampled canvases: coverage and depth are tested per sample,
+/// while a pixel is shaded once at its center.
+pub fn render_triangle_mesh_simd<S: Shader>(
+    face: &ShadedFace<S::Varyings>,
+    canvas: &mut DrawingBuffer,
+    shader: &S,
+    state: &RenderState,
+) {
+    let [p0, p1, p2] = face.get_screen_positions();
+    let area = (p1.x - p0.x) * (p2.y - p0.y) - (p2.x - p0.x) * (p1.y - p0.y);
+    if area == 0.0 {
+        return;
+    }
+    let inv_area = 1.0 / area;
+    let edges = [
+        EdgeFunction::new(p1, p2, inv_area),
+        EdgeFunction::new(p2, p0, inv_area),
+        EdgeFunction::new(p0, p1, inv_area),
+    ];
+
+    let screen_rect = canvas.get_screen_rect();
+    let sample_count = canvas.get_sample_count();
+    let sample_positions = sample_count.get_positions();
+    let sample_radius = sample_count.get_max_offset();
+
+    let (min, max) = (p0.min(p1).min(p2), p0.max(p1).max(p2));
+    let x_range = ((min.x - sample_radius).max(0.0) as usize).max(screen_rect.x)
+        ..((max.x + sample_radius).max(0.0) as usize + 1).min(screen_rect.get_x_range().end);
+    let y_range = ((min.y - sample_radius).max(0.0) as usize).max(screen_rect.y)
+        ..((max.y + sample_radius).max(0.0) as usize + 1).min(screen_rect.get_y_range().end);
+    if x_range.is_empty() || y_range.is_empty() {
+        return;
+    }
+    let x_end = Vec4::splat(x_range.end as f32);
+
+    let z_values = Vec3A::new(p0.z, p1.z, p2.z);
+    let (canvas_width, canvas_height) = (canvas.get_width(), canvas.get_height());
+    let accumulate_transparency =
+        state.blend_mode == BlendMode::Alpha && canvas.is_accumulating_transparency();
+
+    for y in y_range {
+        let y_f32 = y as f32 + 0.5;
+        let local_y = y - screen_rect.y;
+        let row_offset = (canvas_height - local_y - 1) * canvas_width;
+
+        // Conservative span of the pixel centers of the row covered by the triangle,
+        // exact coverage is still decided by the edge tests below
+        let mut row_span = (x_range.start as f32, x_range.end as f32);
+        for edge in edges.iter() {
+            edge.clip_row(y_f32, sample_radius, &mut row_span);
+        }
+        if row_span.0 > row_span.1 {
+            continue;
+        }
+        let row_x_range = ((row_span.0 - 0.5).floor().max(0.0) as usize).max(x_range.start)
+            ..((row_span.1 - 0.5).max(0.0) as usize + 1).min(x_range.end);
+
+        for x in row_x_range.step_by(LANES) {
+            let local_x = x - screen_rect.x;
+            let xs = Vec4::splat(x as f32) + LANE_OFFSETS;
+            let in_span = xs.cmplt(x_end);
+            let lanes_count = LANES.min(x_range.end - x);
+
+            // Coverage and depth test run per sample,
+            // while shading below happens once per pixel
+            let mut samples_masks = [0u32; MAX_SAMPLES];
+            let mut samples_z_depth = [Vec4::ZERO; MAX_SAMPLES];
+            let mut coverage_mask = 0u32;
+            let mut mask = 0u32;
+
+            for (sample, position) in sample_positions.iter().enumerate() {
+                let sample_xs = xs + Vec4::splat(position.x);
+                let sample_y = y_f32 + position.y;
+                let sample_w = [
+                    edges[0].evaluate(sample_xs, sample_y),
+                    edges[1].evaluate(sample_xs, sample_y),
+                    edges[2].evaluate(sample_xs, sample_y),
+                ];
+
+                let coverage: BVec4A = in_span
+                    & sample_w[0].cmpge(Vec4::ZERO)
+                    & sample_w[1].cmpge(Vec4::ZERO)
+                    & sample_w[2].cmpge(Vec4::ZERO);
+                if !coverage.any() {
+                    continue;
+                }
+
+                let z_depth =
+                    sample_w[0] * z_values.x + sample_w[1] * z_values.y + sample_w[2] * z_values.z;
+
+                let z_buffer = canvas.get_sample_buffers(sample).1.get_buffer();
+                let z_stored = if lanes_count == LANES {
+                    Vec4::from_slice(&z_buffer[row_offset + local_x..row_offset + local_x + LANES])
+                } else {
+                    let mut z_stored = Vec4::splat(f32::MAX);
+                    for i in 0..lanes_count {
+                        z_stored[i] = z_buffer[row_offset + local_x + i];
+                    }
+                    z_stored
+                };
+
+                coverage_mask |= coverage.bitmask();
+                samples_masks[sample] =
+                    (coverage & state.depth_compare.compare_lanes(z_depth, z_stored)).bitmask();
+                samples_z_depth[sample] = z_depth;
+                mask |= samples_masks[sample];
+            }
+
+            // Stencil is stored per pixel, so its depth test passes when any of the samples does
+            if let Some(stencil) = &state.stencil {
+                for i in lanes(coverage_mask) {
+                    let p = (local_x + i, local_y);
+                    if !canvas.stencil_test(p, stencil) {
+                        mask &= !(1 << i);
+                    } else if mask & (1 << i) == 0 {
+                        canvas.stencil_update(p, stencil, stencil.depth_fail_op);
+                    }
+                }
+            }
+
+            if mask == 0 {
+                continue;
+            }
+
+            let w = [
+                edges[0].evaluate(xs, y_f32),
+                edges[1].evaluate(xs, y_f32),
+                edges[2].evaluate(xs, y_f32),
+            ];
+            let z_depth = w[0] * z_values.x + w[1] * z_values.y + w[2] * z_values.z;
+
+            let colors = if state.needs_shading() {
+                shader.fragment_lanes(&FragmentLanes {
+                    xs,
+                    y: y_f32,
+                    z_depth,
+                    weights: w,
+                    mask,
+                    face,
+                })
+            } else {
+                [Some(Color::default()); LANES]
+            };
+
+            for i in lanes(mask) {
+                let color = match colors[i] {
+                    Some(color) if state.passes_alpha_test(color) => color,
+                    _ => continue,
+                };
+
+                let p = (local_x + i, local_y);
+                if let Some(stencil) = &state.stencil {
+                    canvas.stencil_update(p, stencil, stencil.pass_op);
+                }
+                if accumulate_transparency {
+                    // Transparency is accumulated once per pixel, so only its center counts,
+                    // partial coverage would show the seams between adjacent faces
+                    if w[0][i] >= 0.0 && w[1][i] >= 0.0 && w[2][i] >= 0.0 {
+                        canvas.accumulate_transparent(p, color, z_depth[i]);
+                    }
+                    continue;
+                }
+
+                for sample in 0..sample_positions.len() {
+                    if samples_masks[sample] & (1 << i) == 0 {
+                        continue;
+                    }
+                    let (color_buffer, z_buffer) = canvas.get_sample_buffers_mut(sample);
+                    state.write(
+                        &mut color_buffer[p],
+                        &mut z_buffer[p],
+                        color,
+                        samples_z_depth[sample][i],
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3A, Vec4};
+
+    use super::render_triangle_mesh_simd;
+    use crate::{
+        plane_buffer::plane_buffer::PlaneBufferCreateOption,
+        visual::{
+            color::color::Color,
+            drawing_buffer::DrawingBuffer,
+            multisampling::SampleCount,
+            rendering::{
+                compare_function::CompareFunction,
+                depth::depth_state::DepthState,
+                render_state::RenderState,
+                shader::shader::{
+                    Fragment, ShadedFace, ShadedVertex, Shader, VertexInput, VertexOutput,
+                },
+                triangle::{
+                    triangle_depth::render_triangle_depth,
+                    triangle_rasterization::render_triangle_mesh,
+                },
+            },
+            vertex::Vertex,
+        },
+    };
+
+    /// Interpolated varyings as the fragment color
+    struct GradientShader;
+
+    impl Shader for GradientShader {
+        type Varyings = Vec3A;
+
+        fn vertex(&self, input: &VertexInput) -> VertexOutput<Vec3A> {
+            VertexOutput {
+                clip_pos: Vec4::from((input.position, 1.0)),
+                varyings: input.normal,
+            }
+        }
+
+        fn fragment(&self, fragment: &Fragment<Vec3A>) -> Option<Color> {
+            Some(Color::from_vec4(Vec4::from((fragment.varyings, 1.0))))
+        }
+    }
+
+    fn face(positions: [(f32, f32, f32); 3]) -> ShadedFace<Vec3A> {
+        let colors = [Vec3A::X, Vec3A::Y, Vec3A::Z];
+        ShadedFace {
+            vertices: [0, 1, 2].map(|i| {
+                let (x, y, z) = positions[i];
+                ShadedVertex {
+                    screen_pos: Vec3A::new(x, y, z),
+                    varyings: colors[i],
+                }
+            }),
+            is_front_facing: true,
+        }
+    }
+
+    #[test]
+    fn scalar_and_simd_rasterizers_match() {
+        // A quad split along its diagonal, overlapped by a nearer triangle
+        let faces = [
+            face([(3.3, 4.6, 10.0), (58.7, 6.2, 10.0), (55.1, 57.8, 10.0)]),
+            face([(3.3, 4.6, 10.0), (55.1, 57.8, 10.0), (5.4, 59.3, 10.0)]),
+            face([(20.2, 12.7, 30.0), (44.9, 30.1, 5.0), (14.6, 47.3, 20.0)]),
+        ];
+        let render = |simd: bool| {
+            let mut canvas = DrawingBuffer::new(64, 64, PlaneBufferCreateOption::Blank);
+            for face in faces.iter() {
+                if simd {
+                    render_triangle_mesh_simd(
+                        face,
+                        &mut canvas,
+                        &GradientShader,
+                        &RenderState::default(),
+                    );
+                } else {
+                    render_triangle_mesh(
+                        face,
+                        &mut canvas,
+                        &GradientShader,
+                        &RenderState::default(),
+                    );
+                }
+            }
+            canvas
+        };
+        let (scalar, simd) = (render(false), render(true));
+
+        let mut covered = 0;
+        let mut mismatched = 0;
+        for (a, b) in scalar.get_buffer().iter().zip(simd.get_buffer()) {
+            if a.alpha == 0 && b.alpha == 0 {
+                continue;
+            }
+            covered += 1;
+            let max_difference = [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.alpha, b.alpha)]
+                .iter()
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap();
+            if max_difference > 1 {
+                mismatched += 1;
+            }
+        }
+
+        // Both sample the pixel centers, only the pixels on the edges may be covered by one
+        assert!(covered > 2500);
+        assert!(
+            mismatched * 100 <= covered,
+            "{} of {} pixels differ",
+            mismatched,
+            covered
+        );
+    }
+
+    #[test]
+    fn depth_pass_matches_shaded_depth() {
+        let faces = [
+            face([(3.3, 4.6, 10.0), (58.7, 6.2, 10.0), (55.1, 57.8, 10.0)]),
+            face([(20.2, 12.7, 30.0), (44.9, 30.1, 5.0), (14.6, 47.3, 20.0)]),
+        ];
+        let mut canvas = DrawingBuffer::new(64, 64, PlaneBufferCreateOption::Blank);
+        let mut depth_buffer = canvas.get_z_buffer().clone();
+        for face in faces.iter() {
+            render_triangle_mesh(face, &mut canvas, &GradientShader, &RenderState::default());
+            let vertices = face
+                .vertices
+                .map(|v| Vertex::new(v.screen_pos, Vec2::ZERO, Vec3A::Z));
+            render_triangle_depth(&vertices, &mut depth_buffer, &DepthState::default(), None);
+        }
+
+        assert_eq!(
+            canvas.get_z_buffer().get_buffer(),
+            depth_buffer.get_buffer()
+        );
+    }
+
+    fn render_both(
+        canvas: &mut DrawingBuffer,
+        face: &ShadedFace<Vec3A>,
+        state: &RenderState,
+        simd: bool,
+    ) {
+        if simd {
+            render_triangle_mesh_simd(face, canvas, &GradientShader, state);
+        } else {
+            render_triangle_mesh(face, canvas, &GradientShader, state);
+        }
+    }
+
+    /// Face covering the whole 8x8 canvas in a single color at constant depth
+    fn flat_face(z: f32, color: Vec3A) -> ShadedFace<Vec3A> {
+        let mut face = face([(-1.0, -1.0, z), (20.0, -1.0, z), (-1.0, 20.0, z)]);
+        for vertex in face.vertices.iter_mut() {
+            vertex.varyings = color;
+        }
+        face
+    }
+
+    #[test]
+    fn depth_compare_functions() {
+        let (far, near) = (flat_face(10.0, Vec3A::X), flat_face(20.0, Vec3A::Y));
+        let (red, green) = (
+            Color::from_rgb_with_alpha(255, 0, 0, 255),
+            Color::from_rgb_with_alpha(0, 255, 0, 255),
+        );
+
+        // The far face is drawn first, then the near one, into the buffer cleared for the test
+        let cases = [
+            (CompareFunction::Never, None),
+            (CompareFunction::Less, Some(red)),
+            (CompareFunction::LessEqual, Some(red)),
+            (CompareFunction::Equal, None),
+            (CompareFunction::NotEqual, Some(green)),
+            (CompareFunction::GreaterEqual, Some(green)),
+            (CompareFunction::Greater, Some(green)),
+            (CompareFunction::Always, Some(green)),
+        ];
+        for simd in [false, true] {
+            for (compare, expected) in cases {
+                let depth = DepthState {
+                    compare,
+                    ..DepthState::default()
+                };
+                let state = RenderState {
+                    depth_compare: compare,
+                    ..RenderState::default()
+                };
+                let mut canvas = DrawingBuffer::new(8, 8, PlaneBufferCreateOption::Blank);
+                canvas.set_clear_depth(depth.get_clear_depth());
+                canvas.clean_all();
+
+                render_both(&mut canvas, &far, &state, simd);
+                render_both(&mut canvas, &near, &state, simd);
+
+                let expected = expected.unwrap_or_default();
+                assert!(
+                    canvas.get_buffer().iter().all(|color| *color == expected),
+                    "{:?} (simd: {}) drew {:?}",
+                    compare,
+                    simd,
+                    canvas[(4, 4)]
+                );
+            }
+
+            // Equal passes where the same depth has already been drawn
+            let mut canvas = DrawingBuffer::new(8, 8, PlaneBufferCreateOption::Blank);
+            render_both(&mut canvas, &far, &RenderState::default(), simd);
+            let equal = RenderState {
+                depth_compare: CompareFunction::Equal,
+                ..RenderState::default()
+            };
+            render_both(&mut canvas, &flat_face(10.0, Vec3A::Y), &equal, simd);
+            render_both(&mut canvas, &near, &equal, simd);
+            assert!(canvas.get_buffer().iter().all(|color| *color == green));
+        }
+    }
+
+    #[test]
+    fn msaa_resolves_coverage_and_nearest_depth() {
+        // Red quad whose right edge crosses the pixel column 4 between its samples
+        let right = 4.55;
+        let mut quad = [
+            face([(-1.0, -1.0, 10.0), (right, -1.0, 10.0), (right, 9.0, 10.0)]),
+            face([(-1.0, -1.0, 10.0), (right, 9.0, 10.0), (-1.0, 9.0, 10.0)]),
+        ];
+        for vertex in quad.iter_mut().flat_map(|face| face.vertices.iter_mut()) {
+            vertex.varyings = Vec3A::X;
+        }
+
+        for compare in [CompareFunction::GreaterEqual, CompareFunction::Less] {
+            let depth = DepthState {
+                compare,
+                ..DepthState::default()
+            };
+            let state = RenderState {
+                depth_compare: compare,
+                ..RenderState::default()
+            };
+            let mut canvas = DrawingBuffer::new(8, 8, PlaneBufferCreateOption::Blank);
+            canvas.set_clear_depth(depth.get_clear_depth());
+            canvas.set_sample_count(SampleCount::X4);
+            canvas.clean_all();
+            for face in quad.iter() {
+                render_triangle_mesh_simd(face, &mut canvas, &GradientShader, &state);
+            }
+            canvas.resolve();
+
+            // Two of the four samples of the column 4 lie left of the edge
+            let expected = [
+                (3, Color::from_rgb_with_alpha(255, 0, 0, 255), 10.0),
+                (4, Color::from_rgb_with_alpha(127, 0, 0, 127), 10.0),
+                (5, Color::default(), depth.get_clear_depth()),
+            ];
+            for (x, color, z_depth) in expected {
+                for y in 0..8 {
+                    assert_eq!(canvas[(x, y)], color, "{:?} at ({}, {})", compare, x, y);
+                    let resolved = canvas.get_z_buffer()[(x, y)];
+                    assert!(
+                        (resolved - z_depth).abs() < 1e-3,
+                        "{:?} at ({}, {}): {}",
+                        compare,
+                        x,
+                        y,
+                        resolved
+                    );
+                }
+            }
+        }
+    }
+}
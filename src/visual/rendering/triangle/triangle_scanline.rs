@@ -0,0 +1,71 @@
+use glam::Vec3A;
+
+use crate::{
+    math::geometry::rect::Rect,
+    visual::rendering::triangle::interpolation_values::InterpolationValues,
+};
+
+/// Scanline traversal of the triangle pixels inside the screen rect,
+/// a pixel is covered when its center is within the triangle.
+/// `visit` gets the pixel position local to the rect,
+/// the screen position of the pixel center with the interpolated depth
+/// and the barycentric weights.
+pub fn scan_triangle(
+    positions: [Vec3A; 3],
+    screen_rect: Rect,
+    mut visit: impl FnMut((usize, usize), Vec3A, Vec3A),
+) {
+    let mut sorted_by_x = [0, 1, 2];
+    sorted_by_x.sort_unstable_by(|&a, &b| positions[a].x.partial_cmp(&positions[b].x).unwrap());
+
+    let barycentric = [Vec3A::X, Vec3A::Y, Vec3A::Z];
+    let [l_i, m_i, r_i] = sorted_by_x;
+    let (l_p, m_p, r_p) = (positions[l_i], positions[m_i], positions[r_i]);
+
+    let (l_v, m_v, r_v) = (
+        InterpolationValues::new(l_p, barycentric[l_i]),
+        InterpolationValues::new(m_p, barycentric[m_i]),
+        InterpolationValues::new(r_p, barycentric[r_i]),
+    );
+
+    let (screen_x_range, screen_y_range) = (screen_rect.get_x_range(), screen_rect.get_y_range());
+    let x_range = first_center(l_p.x).max(screen_x_range.start as i32)
+        ..first_center(r_p.x).min(screen_x_range.end as i32);
+
+    for x in x_range {
+        let center_x = x as f32 + 0.5;
+
+        // Centers before the middle vertex are between the left edges, the rest between the right ones
+        let mut v1 = if center_x < m_p.x {
+            l_v.lerp(m_v, (center_x - l_p.x) / (m_p.x - l_p.x))
+        } else {
+            m_v.lerp(r_v, (center_x - m_p.x) / (r_p.x - m_p.x))
+        };
+        let mut v2 = l_v.lerp(r_v, (center_x - l_p.x) / (r_p.x - l_p.x));
+
+        if v1.y > v2.y {
+            (v1, v2) = (v2, v1);
+        }
+
+        let y_range = first_center(v1.y).max(screen_y_range.start as i32)
+            ..first_center(v2.y).min(screen_y_range.end as i32);
+
+        for y in y_range {
+            let center_y = y as f32 + 0.5;
+            let p = (x as usize - screen_rect.x, y as usize - screen_rect.y);
+
+            let InterpolationValues {
+                z_depth,
+                barycentric,
+                ..
+            } = v1.lerp(v2, (center_y - v1.y) / (v2.y - v1.y));
+
+            visit(p, Vec3A::new(center_x, center_y, z_depth), barycentric);
+        }
+    }
+}
+
+/// First pixel whose center is at or after the coordinate
+fn first_center(coordinate: f32) -> i32 {
+    (coordinate - 0.5).ceil() as i32
+}
@@ -1,34 +1,40 @@
-use glam::Mat4;
-
 use crate::{
     plane_buffer::plane_buffer::PlaneBuffer,
-    visual::rendering::triangle::triangle_depth::render_triangle_depth,
+    visual::rendering::{
+        depth::depth_state::DepthState,
+        matrix::camera_matrixes::CameraMatrixes,
+        triangle::triangle_depth::{render_triangle_depth, AlphaCutout},
+    },
 };
 
 use super::{
     wavefront_obj_processing::calculate_wavefront_faces,
-    wavefront_render_model::WavefrontRenderModel,
+    wavefront_render_model::{ShaderKind, WavefrontRenderModel},
 };
 
+/// Depth of the model faces tested and written with the given state,
+/// the model's own one is only meant for the buffers seen by the camera
 pub fn render_wavefront_depth(
     model: &WavefrontRenderModel,
     depth_buffer: &mut PlaneBuffer<f32>,
-    viewport_matrix: Mat4,
-    projection: Mat4,
-    view_matrix: Mat4,
-    rotation_matrix: Mat4,
+    camera: &CameraMatrixes,
+    depth: &DepthState,
 ) {
     let faces = calculate_wavefront_faces(
         model,
-        viewport_matrix,
-        projection,
-        view_matrix,
-        rotation_matrix,
+        camera,
         0.0..depth_buffer.get_width() as f32,
         0.0..depth_buffer.get_height() as f32,
     );
 
+    let alpha_cutout = model.material.alpha_cutoff.map(|cutoff| AlphaCutout {
+        texture: (model.shader == ShaderKind::Default).then_some(&model.obj.texture),
+        sampler: model.material.sampler,
+        opacity: model.material.opacity,
+        cutoff,
+    });
+
     for face in faces.iter() {
-        render_triangle_depth(&face, depth_buffer);
+        render_triangle_depth(face, depth_buffer, depth, alpha_cutout.as_ref());
     }
 }
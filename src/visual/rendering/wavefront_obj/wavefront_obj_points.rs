@@ -0,0 +1,71 @@
+use glam::{Vec3A, Vec4};
+
+use crate::{
+    math::geometry::apply_transform_matrix::vector_apply_transform_matrix,
+    visual::{
+        color::color::Color,
+        drawing_buffer::DrawingBuffer,
+        rendering::{
+            matrix::camera_matrixes::CameraMatrixes,
+            point::{
+                point_rasterization::draw_point,
+                point_style::{PointColoring, PointStyle},
+            },
+        },
+    },
+};
+
+use super::wavefront_render_model::WavefrontRenderModel;
+
+/// Renders every vertex of the model as a splat, faces are ignored
+pub fn render_wavefront_points(
+    model: &WavefrontRenderModel,
+    canvas: &mut DrawingBuffer,
+    style: &PointStyle,
+    camera: &CameraMatrixes,
+) {
+    let wavefront_obj = &model.obj;
+    let transform_matrix = camera.get_transform_matrix(model.model_matrix);
+    let inverse_transposed_transform_matrix = camera.get_normal_matrix();
+
+    let has_vertex_colors = wavefront_obj.vertex_colors.len() == wavefront_obj.vertices.len();
+    let has_vertex_normals = wavefront_obj.vertex_normals.len() == wavefront_obj.vertices.len();
+    let depth_clip_range = canvas.get_depth_clip_range();
+
+    for (i, vertex) in wavefront_obj.vertices.iter().enumerate() {
+        let clip_pos = transform_matrix * Vec4::from((*vertex, 1.0));
+        if clip_pos.w <= 0.0 {
+            continue;
+        }
+        let screen_pos = Vec3A::from(clip_pos.truncate()) / clip_pos.w;
+        if !depth_clip_range.contains(&screen_pos.z) {
+            continue;
+        }
+
+        let size = if style.size_attenuation {
+            style.size / clip_pos.w
+        } else {
+            style.size
+        };
+
+        let color = match style.coloring {
+            PointColoring::Normal if has_vertex_normals => {
+                let normal = vector_apply_transform_matrix(
+                    wavefront_obj.vertex_normals[i],
+                    inverse_transposed_transform_matrix,
+                )
+                .normalize();
+                to_color(normal * 0.5 + 0.5)
+            }
+            _ if has_vertex_colors => to_color(wavefront_obj.vertex_colors[i]),
+            _ => Color::from_rgb(255, 255, 255),
+        };
+
+        draw_point(screen_pos, canvas, size, style.shape, &color, &model.depth);
+    }
+}
+
+fn to_color(v: Vec3A) -> Color {
+    let v = v.clamp(Vec3A::ZERO, Vec3A::ONE) * 255.0;
+    Color::from_rgb(v.x as u8, v.y as u8, v.z as u8)
+}
@@ -1,28 +1,31 @@
 use std::ops::Range;
 
-use glam::{Mat4, Vec2};
+use glam::{BVec3A, Mat4, Vec2, Vec3A};
 
 use crate::{
     math::geometry::apply_transform_matrix::{
         vector_apply_transform_matrix, vertex_apply_transform_matrix,
     },
-    visual::vertex::Vertex,
+    visual::{
+        rendering::{
+            matrix::camera_matrixes::CameraMatrixes,
+            shader::shader::{ShadedFace, ShadedVertex, Shader, VertexInput},
+        },
+        vertex::Vertex,
+    },
 };
 
 use super::wavefront_render_model::WavefrontRenderModel;
 
 pub fn calculate_wavefront_faces(
     model: &WavefrontRenderModel,
-    viewport_matrix: Mat4,
-    projection: Mat4,
-    view_matrix: Mat4,
-    rotation_matrix: Mat4,
+    camera: &CameraMatrixes,
     x_range: Range<f32>,
     y_range: Range<f32>,
 ) -> Vec<[Vertex; 3]> {
     let wavefront_obj = &model.obj;
-    let transform_matrix = viewport_matrix * projection * model.model_matrix * rotation_matrix * view_matrix;
-    let inverse_transposed_transform_matrix = rotation_matrix.transpose().inverse();
+    let transform_matrix = camera.get_transform_matrix(model.model_matrix);
+    let inverse_transposed_transform_matrix = camera.get_normal_matrix();
 
     let faces = wavefront_obj
         .faces
@@ -54,13 +57,148 @@ pub fn calculate_wavefront_faces(
                 }
             }
             if skip {
-                None
-            } else {
-                Some(screen_vertices)
+                return None;
+            }
+
+            let is_front_facing = model.front_face.is_front_facing(&screen_vertices);
+            if model.cull_mode.culls(is_front_facing) {
+                return None;
             }
+
+            if !is_front_facing && model.double_sided_lighting {
+                for vertex in screen_vertices.iter_mut() {
+                    vertex.normal = -vertex.normal;
+                }
+            }
+
+            Some(screen_vertices)
         })
         .filter(Option::is_some)
         .map(Option::unwrap)
         .collect();
     faces
 }
+
+/// Runs the vertex stage of the shader over the model faces,
+/// then projects them on the screen and culls the same way as `calculate_wavefront_faces`.
+/// Faces with vertices out of the depth range are skipped as well.
+pub fn calculate_shaded_faces<S: Shader>(
+    model: &WavefrontRenderModel,
+    shader: &S,
+    viewport_matrix: Mat4,
+    x_range: Range<f32>,
+    y_range: Range<f32>,
+    z_range: Range<f32>,
+) -> Vec<ShadedFace<S::Varyings>> {
+    let wavefront_obj = &model.obj;
+
+    wavefront_obj
+        .faces
+        .iter()
+        .filter_map(|face| {
+            let vertices = [0, 1, 2].map(|j| {
+                let vertex_idx = face[0][j] as usize;
+                let uv3d = wavefront_obj.vertex_textures[face[1][j] as usize];
+                let output = shader.vertex(&VertexInput {
+                    position: wavefront_obj.vertices[vertex_idx],
+                    uv: Vec2::new(uv3d.x, uv3d.y),
+                    normal: wavefront_obj.vertex_normals[vertex_idx],
+                });
+
+                let clip_pos = viewport_matrix * output.clip_pos;
+                ShadedVertex {
+                    screen_pos: Vec3A::from(clip_pos.truncate()) / clip_pos.w,
+                    varyings: output.varyings,
+                }
+            });
+
+            if vertices.iter().any(|v| {
+                !x_range.contains(&v.screen_pos.x)
+                    || !y_range.contains(&v.screen_pos.y)
+                    || !z_range.contains(&v.screen_pos.z)
+            }) {
+                return None;
+            }
+
+            let mut shaded_face = ShadedFace {
+                vertices,
+                is_front_facing: true,
+            };
+            shaded_face.is_front_facing = model.front_face.is_front_facing(&shaded_face);
+            if model.cull_mode.culls(shaded_face.is_front_facing) {
+                return None;
+            }
+
+            Some(shaded_face)
+        })
+        .collect()
+}
+
+/// Screen space bounds of the model's bounding box, transformed by the vertex stage
+/// of the shader. `None` when the box isn't entirely in front of the viewer.
+pub fn calculate_shaded_bounds<S: Shader>(
+    model: &WavefrontRenderModel,
+    shader: &S,
+    viewport_matrix: Mat4,
+) -> Option<(Vec3A, Vec3A)> {
+    let vertices = &model.obj.vertices;
+    if vertices.is_empty() {
+        return None;
+    }
+    let (box_min, box_max) = vertices.iter().fold(
+        (Vec3A::splat(f32::MAX), Vec3A::splat(f32::MIN)),
+        |(min, max), v| (min.min(*v), max.max(*v)),
+    );
+
+    let mut bounds = (Vec3A::splat(f32::MAX), Vec3A::splat(f32::MIN));
+    for corner in 0..8 {
+        let position = Vec3A::select(
+            BVec3A::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0),
+            box_max,
+            box_min,
+        );
+        let output = shader.vertex(&VertexInput {
+            position,
+            uv: Vec2::ZERO,
+            normal: Vec3A::Z,
+        });
+        let clip_pos = viewport_matrix * output.clip_pos;
+        if clip_pos.w <= 0.0 {
+            return None;
+        }
+        let screen_pos = Vec3A::from(clip_pos.truncate()) / clip_pos.w;
+        bounds = (bounds.0.min(screen_pos), bounds.1.max(screen_pos));
+    }
+    Some(bounds)
+}
+
+/// Screen depth of the model's vertices centroid, larger is closer to the viewer
+pub fn calculate_model_depth(
+    model: &WavefrontRenderModel,
+    camera: &CameraMatrixes,
+) -> f32 {
+    let vertices = &model.obj.vertices;
+    if vertices.is_empty() {
+        return f32::MIN;
+    }
+    let centroid = vertices.iter().fold(Vec3A::ZERO, |sum, v| sum + *v) / vertices.len() as f32;
+    vertex_apply_transform_matrix(centroid, camera.get_transform_matrix(model.model_matrix)).z
+}
+
+/// Lighting space bounds of the model's vertices, `None` for the model without vertices
+pub fn calculate_model_bounds(
+    model: &WavefrontRenderModel,
+    view_matrix: Mat4,
+    rotation_matrix: Mat4,
+) -> Option<(Vec3A, Vec3A)> {
+    let transform_matrix = model.model_matrix * rotation_matrix * view_matrix;
+    model
+        .obj
+        .vertices
+        .iter()
+        .map(|v| vertex_apply_transform_matrix(*v, transform_matrix))
+        .fold(None, |bounds, v| match bounds {
+            Some((min, max)) => Some((v.min(min), v.max(max))),
+            None => Some((v, v)),
+        })
+}
@@ -2,40 +2,61 @@ use glam::Mat4;
 
 use crate::{
     math::geometry::{
-        apply_transform_matrix::vertex_apply_transform_matrix, primitives::line::Line,
+        apply_transform_matrix::{vector_apply_transform_matrix, vertex_apply_transform_matrix},
+        primitives::line::Line,
     },
     visual::{
         color::color::Color,
         drawing_buffer::DrawingBuffer,
+        multisampling::SampleCount,
         rendering::{
+            compare_function::CompareFunction,
+            deferred::{g_buffer::GBuffer, g_buffer_rasterization::render_triangle_g_buffer},
             light_source::{LightSource, LightSourceKind},
-            line::line_rasterization::draw_line,
-            triangle::triangle_rasterization::render_triangle_mesh,
+            line::{line_rasterization::draw_line_styled, line_style::LineStyle},
+            matrix::camera_matrixes::CameraMatrixes,
+            occlusion::hi_z_buffer::HiZBuffer,
+            render_state::RenderState,
+            stencil::stencil_state::StencilState,
+            render_target::{
+                framebuffer::Framebuffer, framebuffer_rasterization::render_triangle_framebuffer,
+            },
+            shader::{
+                default_shader::DefaultShader,
+                multi_target_shader::MultiTargetShader,
+                normal_shader::NormalShader,
+                outline_shader::OutlineShader,
+                shader::{ShadedFace, Shader},
+            },
+            tiling::tiled_rasterizer::TiledRasterizer,
+            triangle::{
+                triangle_rasterization::render_triangle_mesh,
+                triangle_rasterization_simd::render_triangle_mesh_simd,
+            },
         },
+        vertex::ScreenTriangle,
     },
-    wavefront::wavefront_obj::WavefrontObj,
 };
 
 use super::{
-    wavefront_obj_processing::calculate_wavefront_faces,
-    wavefront_render_model::WavefrontRenderModel,
+    wavefront_obj_processing::{calculate_shaded_bounds, calculate_shaded_faces},
+    wavefront_render_model::{ShaderKind, WavefrontRenderModel},
 };
 
+/// Draws edges of the model faces.
+/// With depth offset the edges are tested against the z-buffer,
+/// which has to be filled by the model beforehand.
 pub fn render_wavefront_grid(
-    wavefront_obj: &WavefrontObj,
+    model: &WavefrontRenderModel,
     canvas: &mut DrawingBuffer,
-    viewport_matrix: Mat4,
-    projection: Mat4,
-    view_matrix: Mat4,
-    model_matrix: Mat4,
-    rotation_matrix: Mat4,
-    color: Option<&Color>,
+    camera: &CameraMatrixes,
+    style: &LineStyle,
+    depth_offset: Option<f32>,
 ) {
-    let transform_matrix =
-        viewport_matrix * projection * model_matrix * rotation_matrix * view_matrix;
+    let wavefront_obj = &model.obj;
+    let transform_matrix = camera.get_transform_matrix(model.model_matrix);
 
-    for i in 0..wavefront_obj.faces.len() {
-        let face = &wavefront_obj.faces[i];
+    for face in wavefront_obj.faces.iter() {
         for j in 0..3_usize {
             let first_vertex = vertex_apply_transform_matrix(
                 wavefront_obj.vertices[face[0][j] as usize],
@@ -46,17 +67,11 @@ pub fn render_wavefront_grid(
                 transform_matrix,
             );
 
-            if !canvas.contains(first_vertex.x as usize, second_vertex.y as usize)
-                || !canvas.contains(first_vertex.x as usize, second_vertex.y as usize)
-            {
-                continue;
-            }
-
             let line = Line {
                 begin: first_vertex,
                 end: second_vertex,
             };
-            draw_line(&line, canvas, color);
+            draw_line_styled(&line, canvas, style, depth_offset);
         }
     }
 }
@@ -64,55 +79,306 @@ pub fn render_wavefront_grid(
 pub fn render_wavefront_mesh(
     model: &WavefrontRenderModel,
     canvas: &mut DrawingBuffer,
+    rasterizer: &TiledRasterizer,
     mut lights: Vec<LightSource>,
+    camera: &CameraMatrixes,
+) {
+    rotate_lights(&mut lights, camera.rotation_matrix);
+
+    match model.shader {
+        ShaderKind::Default => {
+            let shader = DefaultShader::new(model, &lights, camera);
+            render_wavefront_shaded(model, canvas, rasterizer, &shader, camera.viewport_matrix);
+        }
+        ShaderKind::Normals => {
+            let shader = NormalShader::new(model, camera);
+            render_wavefront_shaded(model, canvas, rasterizer, &shader, camera.viewport_matrix);
+        }
+    }
+}
+
+/// Renders the model faces with a custom shader
+pub fn render_wavefront_shaded<S: Shader>(
+    model: &WavefrontRenderModel,
+    canvas: &mut DrawingBuffer,
+    rasterizer: &TiledRasterizer,
+    shader: &S,
     viewport_matrix: Mat4,
-    projection: Mat4,
-    view_matrix: Mat4,
-    rotation_matrix: Mat4,
 ) {
-    let wavefront_obj = &model.obj;
+    let state = get_render_state(model);
+
+    // Depth pyramid of what is drawn so far, hidden models and faces skip shading.
+    // Hidden faces still run the depth fail operations of the stencil, so they are all drawn.
+    let hi_z = (rasterizer.uses_occlusion_culling() && state.stencil.is_none())
+        .then(|| HiZBuffer::new(canvas));
+    if let Some(hi_z) = &hi_z {
+        if let Some((min, max)) = calculate_shaded_bounds(model, shader, viewport_matrix) {
+            if hi_z.is_occluded(min, max, state.depth_compare) {
+                return;
+            }
+        }
+    }
+
+    let mut faces = calculate_shaded_faces(
+        model,
+        shader,
+        viewport_matrix,
+        0.0..canvas.get_width() as f32,
+        0.0..canvas.get_height() as f32,
+        canvas.get_depth_clip_range(),
+    );
+
+    apply_polygon_offset(model, &mut faces);
+
+    if let Some(hi_z) = &hi_z {
+        faces.retain(|face| {
+            let [p0, p1, p2] = face.get_screen_positions();
+            !hi_z.is_occluded(p0.min(p1).min(p2), p0.max(p1).max(p2), state.depth_compare)
+        });
+    }
+
+    // Blending isn't commutative, so transparent faces are drawn back to front
+    if model.material.is_transparent() {
+        faces.sort_by(|a, b| face_depth(a).total_cmp(&face_depth(b)));
+    }
+
+    rasterize_faces(&faces, canvas, rasterizer, shader, &state);
+}
+
+/// Depth only draw of the model with the same faces as `render_wavefront_mesh` draws.
+/// Once the depth of the whole scene is in place, just the visible fragments pass
+/// the depth test of the shaded draws, so that each pixel is shaded once.
+/// Models whose depth test fails at the equal depth, which don't write depth
+/// or which use stencil (its operations would run twice) are skipped.
+pub fn render_wavefront_depth_pre_pass(
+    model: &WavefrontRenderModel,
+    canvas: &mut DrawingBuffer,
+    rasterizer: &TiledRasterizer,
+    camera: &CameraMatrixes,
+) {
+    match model.shader {
+        ShaderKind::Default => {
+            let shader = DefaultShader::new(model, &[], camera);
+            render_depth_pre_pass(model, canvas, rasterizer, &shader, camera.viewport_matrix);
+        }
+        ShaderKind::Normals => {
+            let shader = NormalShader::new(model, camera);
+            render_depth_pre_pass(model, canvas, rasterizer, &shader, camera.viewport_matrix);
+        }
+    }
+}
+
+fn render_depth_pre_pass<S: Shader>(
+    model: &WavefrontRenderModel,
+    canvas: &mut DrawingBuffer,
+    rasterizer: &TiledRasterizer,
+    shader: &S,
+    viewport_matrix: Mat4,
+) {
+    let state = get_render_state(model);
+    let passes_equal = matches!(
+        state.depth_compare,
+        CompareFunction::GreaterEqual | CompareFunction::LessEqual
+    );
+    if !state.depth_write || !passes_equal || state.stencil.is_some() {
+        return;
+    }
+
+    let mut faces = calculate_shaded_faces(
+        model,
+        shader,
+        viewport_matrix,
+        0.0..canvas.get_width() as f32,
+        0.0..canvas.get_height() as f32,
+        canvas.get_depth_clip_range(),
+    );
+    apply_polygon_offset(model, &mut faces);
+
+    let depth_state = RenderState {
+        color_write: false,
+        ..state
+    };
+    rasterize_faces(&faces, canvas, rasterizer, shader, &depth_state);
+}
+
+/// Draws the model inflated by the width around the canvas pixels, whose stencil
+/// isn't the reference, e.g. around the model drawn with `StencilState::write(reference)`.
+/// The outline is drawn over the rest of the scene.
+pub fn render_wavefront_outline(
+    model: &WavefrontRenderModel,
+    canvas: &mut DrawingBuffer,
+    rasterizer: &TiledRasterizer,
+    camera: &CameraMatrixes,
+    reference: u8,
+    width: f32,
+    color: Color,
+) {
+    let shader = OutlineShader::new(model, camera, width, color);
+    let state = RenderState {
+        depth_compare: CompareFunction::Always,
+        depth_write: false,
+        stencil: Some(StencilState::test(CompareFunction::NotEqual, reference)),
+        ..RenderState::default()
+    };
+
+    let faces = calculate_shaded_faces(
+        model,
+        &shader,
+        camera.viewport_matrix,
+        0.0..canvas.get_width() as f32,
+        0.0..canvas.get_height() as f32,
+        canvas.get_depth_clip_range(),
+    );
+    rasterize_faces(&faces, canvas, rasterizer, &shader, &state);
+}
+
+/// Renders the model into all of the framebuffer targets at once,
+/// see `MultiTargetShader` implementations for the outputs of each shader
+pub fn render_wavefront_framebuffer(
+    model: &WavefrontRenderModel,
+    framebuffer: &mut Framebuffer,
+    rasterizer: &TiledRasterizer,
+    mut lights: Vec<LightSource>,
+    camera: &CameraMatrixes,
+) {
+    rotate_lights(&mut lights, camera.rotation_matrix);
+
+    let viewport_matrix = camera.viewport_matrix;
+    match model.shader {
+        ShaderKind::Default => {
+            let shader = DefaultShader::new(model, &lights, camera);
+            render_framebuffer_shaded(model, framebuffer, rasterizer, &shader, viewport_matrix);
+        }
+        ShaderKind::Normals => {
+            let shader = NormalShader::new(model, camera);
+            render_framebuffer_shaded(model, framebuffer, rasterizer, &shader, viewport_matrix);
+        }
+    }
+}
+
+fn render_framebuffer_shaded<S: MultiTargetShader>(
+    model: &WavefrontRenderModel,
+    framebuffer: &mut Framebuffer,
+    rasterizer: &TiledRasterizer,
+    shader: &S,
+    viewport_matrix: Mat4,
+) {
+    let state = get_render_state(model);
+
+    let mut faces = calculate_shaded_faces(
+        model,
+        shader,
+        viewport_matrix,
+        0.0..framebuffer.get_width() as f32,
+        0.0..framebuffer.get_height() as f32,
+        f32::MIN..f32::MAX,
+    );
+    apply_polygon_offset(model, &mut faces);
+
+    if model.material.is_transparent() {
+        faces.sort_by(|a, b| face_depth(a).total_cmp(&face_depth(b)));
+    }
+
+    rasterizer.rasterize(framebuffer, &faces, |face, framebuffer| {
+        render_triangle_framebuffer(face, framebuffer, shader, &state);
+    });
+}
+
+/// Geometry pass of the deferred shading, stores unlit surfaces
+/// of the model faces into the G-buffer
+pub fn render_wavefront_g_buffer(
+    model: &WavefrontRenderModel,
+    g_buffer: &mut GBuffer,
+    rasterizer: &TiledRasterizer,
+    camera: &CameraMatrixes,
+) {
+    // Lighting is left to the screen space pass
+    let shader = DefaultShader::new(model, &[], camera);
+    let state = RenderState {
+        depth_compare: model.depth.compare,
+        ..RenderState::from(&model.material)
+    };
+
+    let mut faces = calculate_shaded_faces(
+        model,
+        &shader,
+        camera.viewport_matrix,
+        0.0..g_buffer.get_width() as f32,
+        0.0..g_buffer.get_height() as f32,
+        f32::MIN..f32::MAX,
+    );
+    apply_polygon_offset(model, &mut faces);
+
+    rasterizer.rasterize(g_buffer, &faces, |face, g_buffer| {
+        render_triangle_g_buffer(face, g_buffer, &shader, &state);
+    });
+}
 
+/// Moves light directions and positions into the same (rotated) space as the shaded normals
+pub fn rotate_lights(lights: &mut [LightSource], rotation_matrix: Mat4) {
     for light in lights.iter_mut() {
         match &mut light.kind {
             LightSourceKind::Linear { dir, .. } => {
                 *dir = vertex_apply_transform_matrix(*dir, rotation_matrix.transpose().inverse())
                     .normalize();
             }
+            LightSourceKind::Point { position, .. } => {
+                *position = vertex_apply_transform_matrix(*position, rotation_matrix);
+            }
+            LightSourceKind::Spot { position, dir, .. } => {
+                *position = vertex_apply_transform_matrix(*position, rotation_matrix);
+                *dir = vector_apply_transform_matrix(*dir, rotation_matrix).normalize();
+            }
             _ => (),
         }
     }
+}
 
-    let faces = calculate_wavefront_faces(
-        model,
-        viewport_matrix,
-        projection,
-        view_matrix,
-        rotation_matrix,
-        0.0..canvas.get_width() as f32,
-        0.0..canvas.get_height() as f32,
-    );
+/// State of the model's draws, from its material, depth and stencil settings
+fn get_render_state(model: &WavefrontRenderModel) -> RenderState {
+    let material_state = RenderState::from(&model.material);
+    RenderState {
+        depth_compare: model.depth.compare,
+        depth_write: material_state.depth_write && model.depth.write,
+        stencil: model.stencil,
+        ..material_state
+    }
+}
 
-    for face in faces.iter() {
-        render_triangle_mesh(
-            &face,
-            canvas,
-            &wavefront_obj.texture,
-            &mut lights,
-            if model.use_normal_map {
-                wavefront_obj.normal_map.as_ref()
-            } else {
-                None
-            },
-            if model.use_spec_map {
-                wavefront_obj.spec_map.as_ref()
-            } else {
-                None
-            },
-            if model.use_glow_map {
-                wavefront_obj.glow_map.as_ref()
-            } else {
-                None
-            },
-        );
+fn apply_polygon_offset<V>(model: &WavefrontRenderModel, faces: &mut [ShadedFace<V>]) {
+    if model.depth.offset.is_none() {
+        return;
+    }
+    for face in faces.iter_mut() {
+        let offset = model.depth.offset.calculate(face.get_screen_positions());
+        for vertex in face.vertices.iter_mut() {
+            vertex.screen_pos.z -= offset;
+        }
     }
 }
+
+fn rasterize_faces<S: Shader>(
+    faces: &[ShadedFace<S::Varyings>],
+    canvas: &mut DrawingBuffer,
+    rasterizer: &TiledRasterizer,
+    shader: &S,
+    state: &RenderState,
+) {
+    // Scanline rasterizer has no notion of samples, so multisampled
+    // canvases always go through the edge function rasterizer
+    let use_edge_functions =
+        rasterizer.uses_simd() || canvas.get_sample_count() != SampleCount::X1;
+
+    rasterizer.rasterize(canvas, faces, |face, canvas| {
+        if use_edge_functions {
+            render_triangle_mesh_simd(face, canvas, shader, state);
+        } else {
+            render_triangle_mesh(face, canvas, shader, state);
+        }
+    });
+}
+
+// Larger depth is closer to the viewer
+fn face_depth<T: ScreenTriangle>(face: &T) -> f32 {
+    face.get_screen_positions().iter().map(|p| p.z).sum()
+}
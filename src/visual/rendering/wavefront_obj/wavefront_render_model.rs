@@ -1,6 +1,37 @@
 use glam::Mat4;
 
-use crate::wavefront::wavefront_obj::WavefrontObj;
+use crate::{
+    visual::rendering::{
+        depth::depth_state::DepthState,
+        face_culling::{CullMode, FrontFace},
+        material::Material,
+        point::point_style::PointStyle,
+        stencil::stencil_state::StencilState,
+    },
+    wavefront::wavefront_obj::WavefrontObj,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum RenderMode {
+    Mesh,
+    Points(PointStyle),
+}
+
+/// Shader used for the mesh faces
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderKind {
+    Default,
+    Normals,
+}
+
+impl ShaderKind {
+    pub fn next(&self) -> ShaderKind {
+        match self {
+            ShaderKind::Default => ShaderKind::Normals,
+            ShaderKind::Normals => ShaderKind::Default,
+        }
+    }
+}
 
 #[derive(Clone, Debug, Builder)]
 pub struct WavefrontRenderModel {
@@ -8,19 +39,60 @@ pub struct WavefrontRenderModel {
     pub use_normal_map: bool,
     pub use_spec_map: bool,
     pub use_glow_map: bool,
-    pub use_self_shadowing: bool,
+    /// Drawn into the shadow maps of the lights
+    pub casts_shadows: bool,
+    /// Shaded with the shadow maps of the lights
+    pub receives_shadows: bool,
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    pub double_sided_lighting: bool,
     pub model_matrix: Mat4,
+    pub render_mode: RenderMode,
+    pub shader: ShaderKind,
+    pub material: Material,
+    pub depth: DepthState,
+    /// Stencil test and operations of the model draws, used only with canvas stencil plane
+    pub stencil: Option<StencilState>,
+}
+
+impl WavefrontRenderModel {
+    /// Opaque depth writing meshes of the default shader can be drawn
+    /// through the G-buffer, the rest is drawn forward after the lighting pass
+    pub fn supports_deferred_shading(&self) -> bool {
+        matches!(self.render_mode, RenderMode::Mesh)
+            && self.shader == ShaderKind::Default
+            && !self.material.is_transparent()
+            && self.depth.write
+            && self.stencil.is_none()
+    }
 }
 
 impl From<WavefrontObj> for WavefrontRenderModel {
     fn from(wavefront_obj: WavefrontObj) -> Self {
+        // Scans often come without faces, those are only visible as points
+        let render_mode = if wavefront_obj.faces.is_empty() {
+            RenderMode::Points(PointStyle::default())
+        } else {
+            RenderMode::Mesh
+        };
+
         WavefrontRenderModelBuilder::default()
             .obj(wavefront_obj)
             .use_normal_map(false)
             .use_spec_map(false)
             .use_glow_map(false)
-            .use_self_shadowing(false)
+            .casts_shadows(true)
+            .receives_shadows(false)
+            // Open meshes stay visible from behind, closed ones opt into back-face culling
+            .cull_mode(CullMode::None)
+            .front_face(FrontFace::Ccw)
+            .double_sided_lighting(false)
             .model_matrix(Mat4::IDENTITY)
+            .render_mode(render_mode)
+            .shader(ShaderKind::Default)
+            .material(Material::default())
+            .depth(DepthState::default())
+            .stencil(None)
             .build()
             .unwrap()
     }
@@ -15,6 +15,17 @@ impl Vertex {
     }
 }
 
+/// Triangle which can be placed on the screen, e.g. for binning or culling
+pub trait ScreenTriangle {
+    fn get_screen_positions(&self) -> [Vec3A; 3];
+}
+
+impl ScreenTriangle for [Vertex; 3] {
+    fn get_screen_positions(&self) -> [Vec3A; 3] {
+        [self[0].screen_pos, self[1].screen_pos, self[2].screen_pos]
+    }
+}
+
 impl Deref for Vertex {
     type Target = Vec3A;
     fn deref(&self) -> &Self::Target {
@@ -1,16 +1,21 @@
 use glam::{UVec3, Vec3A};
-use image::DynamicImage;
-
-use crate::plane_buffer::plane_buffer::PlaneBuffer;
+use crate::visual::rendering::texture::texture::Texture;
 
 #[derive(Clone, Debug)]
 pub struct WavefrontObj {
     pub vertices: Vec<Vec3A>,
+    // Optional per-vertex colors in 0..1 range (`v x y z r g b`), empty if not present,
+    // vertices without colors in a colored model are white
+    pub vertex_colors: Vec<Vec3A>,
     pub vertex_textures: Vec<Vec3A>,
     pub vertex_normals: Vec<Vec3A>,
     pub faces: Vec<Vec<UVec3>>,
-    pub texture: DynamicImage,
-    pub normal_map: Option<PlaneBuffer<Vec3A>>,
-    pub spec_map: Option<DynamicImage>,
-    pub glow_map: Option<DynamicImage>
+    pub texture: Texture,
+    pub normal_map: Option<Texture>,
+    pub spec_map: Option<Texture>,
+    pub glow_map: Option<Texture>,
+    /// Roughness in the green and metallic in the blue channel, as in glTF
+    pub metallic_roughness_map: Option<Texture>,
+    /// Ambient occlusion in the red channel
+    pub occlusion_map: Option<Texture>
 }
@@ -1,6 +1,7 @@
 pub struct WaveFrontObjSource {
     pub model_path: &'static str,
-    pub texture_path: &'static str,
+    /// The model is white without the diffuse texture
+    pub texture_path: Option<&'static str>,
     pub normal_map_path: Option<&'static str>,
     pub spec_map_path: Option<&'static str>,
     pub glow_map_path: Option<&'static str>
@@ -9,7 +10,7 @@ pub struct WaveFrontObjSource {
 impl WaveFrontObjSource {
     pub const fn new(
         model_path: &'static str,
-        texture_path: &'static str,
+        texture_path: Option<&'static str>,
         normal_map_path: Option<&'static str>,
         spec_map_path: Option<&'static str>,
         glow_map_path: Option<&'static str>